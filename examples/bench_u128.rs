@@ -0,0 +1,41 @@
+//! Compares hashing `u128` keys through `Hasher::write_u128` with the byte
+//! slice path `FxHasher` used for them before.
+//!
+//! Run with `cargo run --release --example bench_u128`.
+
+extern crate rustc_hash;
+
+use rustc_hash::FxHasher;
+use std::hash::Hasher;
+use std::hint::black_box;
+use std::time::Instant;
+
+const KEYS: u128 = 1 << 16;
+const ROUNDS: usize = 2_000;
+
+fn bench(name: &str, keys: &[u128], f: impl Fn(&mut FxHasher, u128)) {
+    let start = Instant::now();
+    let mut sum = 0u64;
+    for _ in 0..ROUNDS {
+        for &key in keys {
+            let mut hasher = FxHasher::default();
+            f(&mut hasher, black_box(key));
+            sum = sum.wrapping_add(hasher.finish());
+        }
+    }
+    let elapsed = start.elapsed();
+    black_box(sum);
+    println!(
+        "{:<12} {:>6.3} ns/key",
+        name,
+        elapsed.as_secs_f64() * 1e9 / (keys.len() * ROUNDS) as f64
+    );
+}
+
+fn main() {
+    let keys: Vec<u128> = (0..KEYS)
+        .map(|i| i.wrapping_mul(0x9e3779b97f4a7c15f39cc0605cedc835))
+        .collect();
+    bench("write", &keys, |h, i| h.write(&i.to_ne_bytes()));
+    bench("write_u128", &keys, |h, i| h.write_u128(i));
+}
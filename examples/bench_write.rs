@@ -0,0 +1,42 @@
+//! Compares the throughput of `Hasher::write` for `v1::FxHasher` and
+//! `v2::FxHasher`, whose long buffers go through a separate block path.
+//!
+//! Run with `cargo run --release --example bench_write`.
+
+extern crate rustc_hash;
+
+use rustc_hash::{v1, v2};
+use std::hash::Hasher;
+use std::hint::black_box;
+use std::time::Instant;
+
+// Bytes hashed for every measurement, split into buffers of the given size.
+const TOTAL: usize = 1 << 30;
+
+fn bench<H: Hasher + Default>(buffer: &[u8]) -> f64 {
+    let rounds = TOTAL / buffer.len();
+    let start = Instant::now();
+    for _ in 0..rounds {
+        let mut hasher = H::default();
+        hasher.write(black_box(buffer));
+        black_box(hasher.finish());
+    }
+    let seconds = start.elapsed().as_secs_f64();
+    (rounds * buffer.len()) as f64 / seconds / (1 << 30) as f64
+}
+
+fn main() {
+    let data: Vec<u8> = (0..1 << 16)
+        .map(|i: u32| (i.wrapping_mul(2654435761) >> 24) as u8)
+        .collect();
+    println!("{:>8} {:>12} {:>12}", "bytes", "v1 GiB/s", "v2 GiB/s");
+    for &len in &[8, 64, 1 << 10, 1 << 16] {
+        let buffer = &data[..len];
+        println!(
+            "{:>8} {:>12.2} {:>12.2}",
+            len,
+            bench::<v1::FxHasher>(buffer),
+            bench::<v2::FxHasher>(buffer)
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{
+    parse_quote, Attribute, Data, DeriveInput, Error, Field, Fields, GenericParam, Ident, Index,
+    Type,
+};
+
+/// Returns whether the field has `#[stable_hasher(ignore)]`.
+fn ignored(field: &Field) -> Result<bool, Error> {
+    let mut ignore = false;
+    for attr in &field.attrs {
+        if attr.path().is_ident("stable_hasher") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("ignore") {
+                    ignore = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported stable_hasher attribute on a field"))
+                }
+            })?;
+        }
+    }
+    Ok(ignore)
+}
+
+/// Returns the context given with `#[stable_hasher(context = Ctx)]`.
+fn context(attrs: &[Attribute]) -> Result<Option<Type>, Error> {
+    let mut context = None;
+    for attr in attrs {
+        if attr.path().is_ident("stable_hasher") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("context") {
+                    context = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported stable_hasher attribute"))
+                }
+            })?;
+        }
+    }
+    Ok(context)
+}
+
+/// Hashes the fields that are not ignored, given as references to their
+/// values.
+fn hash_fields<'a>(
+    fields: impl Iterator<Item = (TokenStream, &'a Field)>,
+    ctx: &TokenStream,
+) -> Result<TokenStream, Error> {
+    let mut out = TokenStream::new();
+    for (value, field) in fields {
+        if !ignored(field)? {
+            out.extend(quote! {
+                ::rustc_hash::HashStable::<#ctx>::hash_stable(#value, ctx, hasher);
+            });
+        }
+    }
+    Ok(out)
+}
+
+pub fn expand(mut input: DeriveInput) -> Result<TokenStream, Error> {
+    let context = context(&input.attrs)?;
+    let ctx = match context {
+        Some(ref ty) => quote!(#ty),
+        None => quote!(__Ctx),
+    };
+
+    let body = match input.data {
+        Data::Struct(ref data) => {
+            let fields = data.fields.iter().enumerate().map(|(i, field)| {
+                let value = match field.ident {
+                    Some(ref ident) => quote!(&self.#ident),
+                    None => {
+                        let index = Index::from(i);
+                        quote!(&self.#index)
+                    }
+                };
+                (value, field)
+            });
+            hash_fields(fields, &ctx)?
+        }
+        Data::Enum(ref data) => {
+            let mut arms = TokenStream::new();
+            for (index, variant) in data.variants.iter().enumerate() {
+                let name = &variant.ident;
+                let bindings: Vec<Ident> = (0..variant.fields.len())
+                    .map(|i| Ident::new(&format!("__field{}", i), Span::call_site()))
+                    .collect();
+                let pattern = match variant.fields {
+                    Fields::Named(ref fields) => {
+                        let names = fields.named.iter().map(|f| &f.ident);
+                        quote!({ #(#names: ref #bindings),* })
+                    }
+                    Fields::Unnamed(_) => quote!(( #(ref #bindings),* )),
+                    Fields::Unit => quote!(),
+                };
+                let fields = bindings
+                    .iter()
+                    .zip(variant.fields.iter())
+                    .map(|(binding, field)| (quote!(#binding), field));
+                let hash = hash_fields(fields, &ctx)?;
+                let index = index as u64;
+                arms.extend(quote! {
+                    Self::#name #pattern => {
+                        ::core::hash::Hasher::write_u64(hasher, #index);
+                        #hash
+                    }
+                });
+            }
+            quote! {
+                match *self {
+                    #arms
+                }
+            }
+        }
+        Data::Union(ref data) => {
+            return Err(Error::new(
+                data.union_token.span,
+                "HashStable cannot be derived for unions",
+            ));
+        }
+    };
+
+    for param in &mut input.generics.params {
+        if let GenericParam::Type(ref mut param) = *param {
+            param
+                .bounds
+                .push(parse_quote!(::rustc_hash::HashStable<#ctx>));
+        }
+    }
+    let name = &input.ident;
+    let (_, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut impl_generics = input.generics.clone();
+    if context.is_none() {
+        impl_generics.params.push(parse_quote!(__Ctx: ?Sized));
+    }
+    let (impl_generics, _, _) = impl_generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::rustc_hash::HashStable<#ctx> for #name #ty_generics #where_clause {
+            #[inline]
+            #[allow(unused_variables)]
+            fn hash_stable(
+                &self,
+                ctx: &mut #ctx,
+                hasher: &mut ::rustc_hash::StableHasher,
+            ) {
+                #body
+            }
+        }
+    })
+}
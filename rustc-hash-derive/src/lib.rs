@@ -0,0 +1,226 @@
+// Copyright 2015 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `#[derive(FxHash)]` and `#[derive(HashStable)]`, re-exported by
+//! `rustc-hash` with its `derive` feature.
+//!
+//! The derived `FxHash` implementations feed an `FxHasher` directly through
+//! its inherent methods. Consecutive fields of type `u8`, `i8`, `bool`, `u16`,
+//! `i16`, `u32`, `i32` and `char`, as well as the discriminant of an enum, are
+//! packed into a single `u64`, which is mixed bijectively and written at once.
+//! Other fields are hashed with their own `FxHash` implementation.
+//!
+//! With `#[fx_hash(bridge)]`, a `Hash` implementation writing the `FxHash`
+//! digest of the value is derived as well, so that the type can be used as
+//! the key of an `FxHashMap`.
+//!
+//! The derived `HashStable` implementations hash the fields in order, after
+//! the index of the variant for an enum. They are generic over the context
+//! unless one is given with `#[stable_hasher(context = Ctx)]`, and skip the
+//! fields marked with `#[stable_hasher(ignore)]`.
+
+mod hash_stable;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Error, Fields, GenericParam, Ident, Index,
+    Type,
+};
+
+#[proc_macro_derive(FxHash, attributes(fx_hash))]
+pub fn derive_fx_hash(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+#[proc_macro_derive(HashStable, attributes(stable_hasher))]
+pub fn derive_hash_stable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match hash_stable::expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(mut input: DeriveInput) -> Result<TokenStream2, Error> {
+    let mut bridge = false;
+    for attr in &input.attrs {
+        if attr.path().is_ident("fx_hash") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("bridge") {
+                    bridge = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported fx_hash attribute"))
+                }
+            })?;
+        }
+    }
+
+    let body = match input.data {
+        Data::Struct(ref data) => {
+            let fields = data
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(i, field)| {
+                    let access = match field.ident {
+                        Some(ref ident) => quote!(&self.#ident),
+                        None => {
+                            let index = Index::from(i);
+                            quote!(&self.#index)
+                        }
+                    };
+                    (access, &field.ty)
+                })
+                .collect();
+            hash_fields(fields, None)
+        }
+        Data::Enum(ref data) => {
+            let bits = if data.variants.len() <= 1 << 8 { 8 } else { 32 };
+            let arms = data.variants.iter().enumerate().map(|(index, variant)| {
+                let name = &variant.ident;
+                let bindings: Vec<Ident> = (0..variant.fields.len())
+                    .map(|i| Ident::new(&format!("__field{}", i), Span::call_site()))
+                    .collect();
+                let pattern = match variant.fields {
+                    Fields::Named(ref fields) => {
+                        let names = fields.named.iter().map(|f| &f.ident);
+                        quote!({ #(#names: ref #bindings),* })
+                    }
+                    Fields::Unnamed(_) => quote!(( #(ref #bindings),* )),
+                    Fields::Unit => quote!(),
+                };
+                let fields = bindings
+                    .iter()
+                    .zip(variant.fields.iter())
+                    .map(|(binding, field)| (quote!(#binding), &field.ty))
+                    .collect();
+                let index = index as u64;
+                let hash = hash_fields(fields, Some((quote!(#index), bits)));
+                quote!(Self::#name #pattern => { #hash })
+            });
+            quote! {
+                match *self {
+                    #(#arms)*
+                }
+            }
+        }
+        Data::Union(ref data) => {
+            return Err(Error::new(
+                data.union_token.span,
+                "FxHash cannot be derived for unions",
+            ));
+        }
+    };
+
+    for param in &mut input.generics.params {
+        if let GenericParam::Type(ref mut param) = *param {
+            param.bounds.push(parse_quote!(::rustc_hash::FxHash));
+        }
+    }
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let bridge = if bridge {
+        quote! {
+            impl #impl_generics ::core::hash::Hash for #name #ty_generics #where_clause {
+                #[inline]
+                fn hash<__H: ::core::hash::Hasher>(&self, state: &mut __H) {
+                    let mut hasher = ::rustc_hash::FxHasher::default();
+                    ::rustc_hash::FxHash::fx_hash(self, &mut hasher);
+                    state.write_u64(hasher.finish());
+                }
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::rustc_hash::FxHash for #name #ty_generics #where_clause {
+            #[inline]
+            fn fx_hash(&self, hasher: &mut ::rustc_hash::FxHasher) {
+                #body
+            }
+        }
+
+        #bridge
+    })
+}
+
+/// Returns the width in bits of the types that are packed with their
+/// neighbours, and the conversion of a reference to such a value to `u64`.
+fn packed(ty: &Type, value: &TokenStream2) -> Option<(u32, TokenStream2)> {
+    let ident = match *ty {
+        Type::Path(ref path) if path.qself.is_none() => path.path.get_ident()?,
+        _ => return None,
+    };
+    let packed = match &*ident.to_string() {
+        "u8" | "bool" => (8, quote!((*#value as u64))),
+        "i8" => (8, quote!((*#value as u8 as u64))),
+        "u16" => (16, quote!((*#value as u64))),
+        "i16" => (16, quote!((*#value as u16 as u64))),
+        "u32" | "char" => (32, quote!((*#value as u32 as u64))),
+        "i32" => (32, quote!((*#value as u32 as u64))),
+        _ => return None,
+    };
+    Some(packed)
+}
+
+/// Hashes `fields`, given as references to their values, after an optional
+/// prefix of the given width in bits.
+fn hash_fields(
+    fields: Vec<(TokenStream2, &Type)>,
+    prefix: Option<(TokenStream2, u32)>,
+) -> TokenStream2 {
+    let mut out = TokenStream2::new();
+    let mut word: Vec<TokenStream2> = Vec::new();
+    let mut bits = 0;
+    if let Some((prefix, width)) = prefix {
+        word.push(quote!((#prefix as u64)));
+        bits = width;
+    }
+    for (value, ty) in fields {
+        match packed(ty, &value) {
+            Some((width, value)) => {
+                if bits + width > 64 {
+                    flush(&mut out, &mut word);
+                    bits = 0;
+                }
+                word.push(if bits == 0 {
+                    value
+                } else {
+                    quote!((#value << #bits))
+                });
+                bits += width;
+            }
+            None => {
+                flush(&mut out, &mut word);
+                bits = 0;
+                out.extend(quote!(::rustc_hash::FxHash::fx_hash(#value, hasher);));
+            }
+        }
+    }
+    flush(&mut out, &mut word);
+    out
+}
+
+fn flush(out: &mut TokenStream2, word: &mut Vec<TokenStream2>) {
+    if !word.is_empty() {
+        out.extend(quote!(::rustc_hash::__write_packed(hasher, #(#word)|*);));
+        word.clear();
+    }
+}
@@ -0,0 +1,399 @@
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::iter::FromIterator;
+use core::ops::Index;
+use std::collections::hash_map::{self, DefaultHasher, RandomState};
+use std::collections::HashMap;
+use std::vec::Vec;
+
+use crate::{FxBuildHasher, FxHasher};
+
+/// The number of counters of the histogram of the low bits of the hashes.
+const LOAD_BUCKETS: usize = 1024;
+
+/// A counter above `MAX_LOAD_FACTOR` times the average plus `MAX_LOAD_SLACK`
+/// means the hashes are clustered. The slack is large enough for the uneven
+/// start of sequences of strings such as `item0`, `item1`, ..., and small
+/// enough to bound the time spent probing before the rehash.
+const MAX_LOAD_FACTOR: usize = 8;
+const MAX_LOAD_SLACK: usize = 256;
+
+/// The `BuildHasher` of an `FxAdaptiveMap`, before and after the rehash.
+#[derive(Clone, Debug)]
+enum AdaptiveState {
+    Fx(FxBuildHasher),
+    Sip(RandomState),
+}
+
+enum AdaptiveHasher {
+    Fx(FxHasher),
+    Sip(DefaultHasher),
+}
+
+impl Default for AdaptiveState {
+    #[inline]
+    fn default() -> AdaptiveState {
+        AdaptiveState::Fx(FxBuildHasher)
+    }
+}
+
+impl BuildHasher for AdaptiveState {
+    type Hasher = AdaptiveHasher;
+
+    #[inline]
+    fn build_hasher(&self) -> AdaptiveHasher {
+        match self {
+            AdaptiveState::Fx(state) => AdaptiveHasher::Fx(state.build_hasher()),
+            AdaptiveState::Sip(state) => AdaptiveHasher::Sip(state.build_hasher()),
+        }
+    }
+}
+
+macro_rules! forward {
+    ($($name:ident($ty:ty),)*) => {
+        $(
+            #[inline]
+            fn $name(&mut self, i: $ty) {
+                match self {
+                    AdaptiveHasher::Fx(hasher) => hasher.$name(i),
+                    AdaptiveHasher::Sip(hasher) => hasher.$name(i),
+                }
+            }
+        )*
+    };
+}
+
+impl Hasher for AdaptiveHasher {
+    forward! {
+        write(&[u8]),
+        write_u8(u8),
+        write_u16(u16),
+        write_u32(u32),
+        write_u64(u64),
+        write_u128(u128),
+        write_usize(usize),
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        match self {
+            AdaptiveHasher::Fx(hasher) => hasher.finish(),
+            AdaptiveHasher::Sip(hasher) => hasher.finish(),
+        }
+    }
+}
+
+/// A hash map that uses the `fx` hash algorithm until its keys collide too
+/// much, and then rebuilds itself with the randomly keyed SipHash of
+/// `std::collections::HashMap`.
+///
+/// `FxHasher` is easy to collide on purpose, so an `FxHashMap` whose keys come
+/// from untrusted input can be made to probe through all its entries on every
+/// lookup. An `FxAdaptiveMap` keeps a histogram of the low bits of the hashes
+/// of its keys, which are the bits selecting the buckets. As soon as one
+/// value of these bits is shared by more than 8 times the average number of
+/// keys plus 256, the map moves its entries to a map using `RandomState`,
+/// which stays in use until the map is dropped, even if it is cleared.
+/// `is_rehashed` tells whether this happened.
+///
+/// While the map uses `FxHasher`, inserting and removing a key hashes it
+/// twice, once to update the histogram and once in the map. Lookups hash it
+/// once. The entry API is not provided, since it would insert keys without
+/// updating the histogram.
+///
+/// # Example
+///
+/// ```rust
+/// use rustc_hash::FxAdaptiveMap;
+///
+/// // Keys spread over the buckets keep the fast hash.
+/// let map: FxAdaptiveMap<u64, u64> = (0..100_000).map(|i| (i, i)).collect();
+/// assert!(!map.is_rehashed());
+///
+/// // Keys whose `FxHasher` hashes all end with the same bits do not.
+/// let shift = usize::BITS / 2;
+/// let mut map = FxAdaptiveMap::new();
+/// for i in 0..10_000usize {
+///     map.insert(i << shift, i);
+/// }
+/// assert!(map.is_rehashed());
+/// assert_eq!(map.len(), 10_000);
+/// assert_eq!(map[&(42 << shift)], 42);
+/// ```
+#[derive(Clone)]
+pub struct FxAdaptiveMap<K, V> {
+    inner: HashMap<K, V, AdaptiveState>,
+    // The number of keys for each value of the low bits of their hashes,
+    // allocated on the first insertion and freed by the rehash.
+    loads: Vec<u32>,
+}
+
+impl<K, V> FxAdaptiveMap<K, V> {
+    /// Creates an empty map using `FxHasher`.
+    #[inline]
+    pub fn new() -> FxAdaptiveMap<K, V> {
+        FxAdaptiveMap {
+            inner: HashMap::default(),
+            loads: Vec::new(),
+        }
+    }
+
+    /// Creates an empty map using `FxHasher`, with space for at least
+    /// `capacity` entries.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> FxAdaptiveMap<K, V> {
+        FxAdaptiveMap {
+            inner: HashMap::with_capacity_and_hasher(capacity, Default::default()),
+            loads: Vec::new(),
+        }
+    }
+
+    /// Returns `true` if the keys collided too much and the map now uses
+    /// SipHash.
+    #[inline]
+    pub fn is_rehashed(&self) -> bool {
+        match self.inner.hasher() {
+            AdaptiveState::Fx(_) => false,
+            AdaptiveState::Sip(_) => true,
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Removes all the entries, keeping the hasher in use.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.inner.clear();
+        self.loads = Vec::new();
+    }
+
+    /// Returns an iterator over the entries, in arbitrary order.
+    #[inline]
+    pub fn iter(&self) -> hash_map::Iter<'_, K, V> {
+        self.inner.iter()
+    }
+
+    /// Returns an iterator over the entries with mutable references to the
+    /// values, in arbitrary order.
+    #[inline]
+    pub fn iter_mut(&mut self) -> hash_map::IterMut<'_, K, V> {
+        self.inner.iter_mut()
+    }
+
+    /// Returns an iterator over the keys, in arbitrary order.
+    #[inline]
+    pub fn keys(&self) -> hash_map::Keys<'_, K, V> {
+        self.inner.keys()
+    }
+
+    /// Returns an iterator over the values, in arbitrary order.
+    #[inline]
+    pub fn values(&self) -> hash_map::Values<'_, K, V> {
+        self.inner.values()
+    }
+
+    /// Returns an iterator over mutable references to the values, in
+    /// arbitrary order.
+    #[inline]
+    pub fn values_mut(&mut self) -> hash_map::ValuesMut<'_, K, V> {
+        self.inner.values_mut()
+    }
+}
+
+impl<K: Eq + Hash, V> FxAdaptiveMap<K, V> {
+    /// Returns the `FxHasher` hash of a key, or `None` after the rehash.
+    #[inline]
+    fn fx_hash<Q: Hash + ?Sized>(&self, key: &Q) -> Option<u64> {
+        match self.inner.hasher() {
+            AdaptiveState::Fx(state) => Some(state.hash_one(key)),
+            AdaptiveState::Sip(_) => None,
+        }
+    }
+
+    /// Inserts a key-value pair, returning the previous value of the key.
+    ///
+    /// This may move all the entries to a map using SipHash.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let hash = self.fx_hash(&key);
+        let old = self.inner.insert(key, value);
+        if let (Some(hash), None) = (hash, &old) {
+            self.record_insert(hash);
+        }
+        old
+    }
+
+    /// Removes a key, returning its value.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.fx_hash(key);
+        let old = self.inner.remove(key);
+        if let (Some(hash), Some(_)) = (hash, &old) {
+            self.loads[hash as usize % LOAD_BUCKETS] -= 1;
+        }
+        old
+    }
+
+    /// Returns a reference to the value corresponding to `key`.
+    #[inline]
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.get(key)
+    }
+
+    /// Returns a mutable reference to the value corresponding to `key`.
+    #[inline]
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.get_mut(key)
+    }
+
+    /// Returns the key-value pair corresponding to `key`.
+    #[inline]
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.get_key_value(key)
+    }
+
+    /// Returns `true` if the map contains `key`.
+    #[inline]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.contains_key(key)
+    }
+
+    /// Keeps only the entries for which `f` returns `true`.
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut f: F) {
+        let state = match self.inner.hasher() {
+            AdaptiveState::Fx(state) => *state,
+            AdaptiveState::Sip(_) => return self.inner.retain(f),
+        };
+        let loads = &mut self.loads;
+        self.inner.retain(|key, value| {
+            let keep = f(key, value);
+            if !keep {
+                loads[state.hash_one(key) as usize % LOAD_BUCKETS] -= 1;
+            }
+            keep
+        });
+    }
+
+    fn record_insert(&mut self, hash: u64) {
+        if self.loads.is_empty() {
+            self.loads = std::vec![0; LOAD_BUCKETS];
+        }
+        let load = &mut self.loads[hash as usize % LOAD_BUCKETS];
+        *load += 1;
+        let max_load = MAX_LOAD_FACTOR * self.inner.len() / LOAD_BUCKETS + MAX_LOAD_SLACK;
+        if *load as usize > max_load {
+            self.rehash();
+        }
+    }
+
+    #[cold]
+    fn rehash(&mut self) {
+        let state = AdaptiveState::Sip(RandomState::new());
+        let mut inner = HashMap::with_capacity_and_hasher(self.inner.len(), state);
+        inner.extend(self.inner.drain());
+        self.inner = inner;
+        self.loads = Vec::new();
+    }
+}
+
+impl<K, V> Default for FxAdaptiveMap<K, V> {
+    #[inline]
+    fn default() -> FxAdaptiveMap<K, V> {
+        FxAdaptiveMap::new()
+    }
+}
+
+impl<K: Eq + Hash, V: PartialEq> PartialEq for FxAdaptiveMap<K, V> {
+    #[inline]
+    fn eq(&self, other: &FxAdaptiveMap<K, V>) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<K: Eq + Hash, V: Eq> Eq for FxAdaptiveMap<K, V> {}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for FxAdaptiveMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<K, Q, V> Index<&Q> for FxAdaptiveMap<K, V>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: Eq + Hash + ?Sized,
+{
+    type Output = V;
+
+    #[inline]
+    fn index(&self, key: &Q) -> &V {
+        &self.inner[key]
+    }
+}
+
+impl<K: Eq + Hash, V> FromIterator<(K, V)> for FxAdaptiveMap<K, V> {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> FxAdaptiveMap<K, V> {
+        let mut map = FxAdaptiveMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Eq + Hash, V> Extend<(K, V)> for FxAdaptiveMap<K, V> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a FxAdaptiveMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = hash_map::Iter<'a, K, V>;
+
+    #[inline]
+    fn into_iter(self) -> hash_map::Iter<'a, K, V> {
+        self.inner.iter()
+    }
+}
+
+impl<K, V> IntoIterator for FxAdaptiveMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = hash_map::IntoIter<K, V>;
+
+    #[inline]
+    fn into_iter(self) -> hash_map::IntoIter<K, V> {
+        self.inner.into_iter()
+    }
+}
@@ -0,0 +1,329 @@
+//! Inputs with a chosen `FxHasher` hash, to test code against hash flooding.
+//!
+//! Each word added to an [`FxHasher`] updates its state with
+//! `(state.rotate_left(5) ^ word) * K`, where `K` is odd. This step can be
+//! undone: from any state, exactly one word leads to a given hash, which is
+//! `(hash * K⁻¹) ^ state.rotate_left(5)`. The functions of this module choose
+//! the first words of an input freely, so that the inputs are all distinct,
+//! and compute the last one to reach the target hash.
+//!
+//! The inputs are built for the default `FxHasher`, which is also the
+//! [`v1::FxHasher`](crate::v1::FxHasher). They start from a `prefix` hasher,
+//! which holds everything written before them: a seed, the fields hashed
+//! before, or the length of a slice. A map's own hasher, as returned by
+//! `BuildHasher::build_hasher`, can be passed to target that map. The target
+//! is truncated to the width of `usize`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::hash::BuildHasher;
+//! use rustc_hash::{collisions, FxAdaptiveMap, FxBuildHasher, FxHashSet};
+//!
+//! let prefix = FxBuildHasher.build_hasher();
+//! let keys: Vec<String> = collisions::strings(&prefix, 0, 16).take(1000).collect();
+//!
+//! // All the keys are distinct, but have the same hash in an `FxHashSet`.
+//! let set: FxHashSet<&String> = keys.iter().collect();
+//! assert_eq!(set.len(), 1000);
+//! assert!(keys.iter().all(|key| FxBuildHasher.hash_one(key) == 0));
+//!
+//! // An `FxAdaptiveMap` notices it and stops using `FxHasher`.
+//! let map: FxAdaptiveMap<&str, usize> = keys.iter().map(|key| (key.as_str(), 0)).collect();
+//! assert!(map.is_rehashed());
+//! ```
+
+use core::mem::size_of;
+use std::string::String;
+use std::vec::Vec;
+
+use crate::v1::{FxHasher, K};
+
+const WORD: usize = size_of::<usize>();
+
+/// The inverse of `K` modulo `2^usize::BITS`. An odd number is its own
+/// inverse modulo 8, and every step of Newton's iteration doubles the number
+/// of correct low bits, from 3 to more than 64.
+const K_INV: usize = {
+    let mut inv = K;
+    let mut i = 0;
+    while i < 5 {
+        inv = inv.wrapping_mul(2usize.wrapping_sub(K.wrapping_mul(inv)));
+        i += 1;
+    }
+    inv
+};
+
+/// The characters of the free part of the strings, from which a counter is
+/// written in base 62.
+const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Returns the word that takes `state` to `target`.
+#[inline]
+fn solve(state: usize, target: usize) -> usize {
+    target.wrapping_mul(K_INV) ^ state.rotate_left(5)
+}
+
+/// Returns the state that `word` takes to `target`.
+#[inline]
+fn unsolve(target: usize, word: usize) -> usize {
+    (target.wrapping_mul(K_INV) ^ word).rotate_right(5)
+}
+
+/// Returns the `usize` that makes `prefix` finish with `target` once it is
+/// written with `write_usize`.
+///
+/// # Example
+///
+/// ```rust
+/// use rustc_hash::{collisions, FxHasher};
+///
+/// let mut hasher = FxHasher::default();
+/// hasher.write_u32(22);
+/// let word = collisions::last_usize(&hasher, 0x1234);
+/// hasher.write_usize(word);
+/// assert_eq!(hasher.finish(), 0x1234);
+/// ```
+#[inline]
+pub fn last_usize(prefix: &FxHasher, target: u64) -> usize {
+    solve(prefix.hash, target as usize)
+}
+
+/// Returns the `u64` that makes `prefix` finish with `target` once it is
+/// written with `write_u64`.
+#[cfg(target_pointer_width = "64")]
+#[inline]
+fn last_u64(prefix: &FxHasher, target: usize) -> u64 {
+    solve(prefix.hash, target) as u64
+}
+
+/// Returns the `u64` that makes `prefix` finish with `target` once it is
+/// written with `write_u64`, which adds its low half and then its high half.
+#[cfg(target_pointer_width = "32")]
+#[inline]
+fn last_u64(prefix: &FxHasher, target: usize) -> u64 {
+    let mut hasher = prefix.clone();
+    hasher.write_usize(0);
+    (solve(hasher.hash, target) as u64) << 32
+}
+
+/// Returns an iterator over distinct sequences of `len` integers that make
+/// `prefix` finish with `target` once they are written with `write_u64`.
+///
+/// This is also how tuples of `u64` are hashed. A `Vec<u64>` or `[u64]`
+/// writes its length first, with `write_usize`, which then has to be part of
+/// the prefix.
+///
+/// # Panics
+///
+/// Panics if `len` is less than 2.
+///
+/// # Example
+///
+/// ```rust
+/// use rustc_hash::{collisions, fx_hash_one, FxHasher};
+///
+/// let mut prefix = FxHasher::default();
+/// prefix.write_usize(3);
+/// for seq in collisions::u64_sequences(&prefix, 42, 3).take(100) {
+///     assert_eq!(fx_hash_one(&seq), 42);
+/// }
+///
+/// let pairs = collisions::u64_sequences(&FxHasher::default(), 42, 2);
+/// for seq in pairs.take(100) {
+///     assert_eq!(fx_hash_one(&(seq[0], seq[1])), 42);
+/// }
+/// ```
+pub fn u64_sequences(prefix: &FxHasher, target: u64, len: usize) -> U64Sequences {
+    assert!(
+        len >= 2,
+        "a sequence needs at least 2 integers to be chosen freely"
+    );
+    U64Sequences {
+        prefix: prefix.clone(),
+        target: target as usize,
+        len,
+        counter: 0,
+    }
+}
+
+/// Returns an iterator over distinct byte strings of `len` bytes that make
+/// `prefix` finish with `target` once they are written with `Hasher::write`.
+///
+/// A `Vec<u8>` or `[u8]` writes its length first, with `write_usize`, which
+/// then has to be part of the prefix. The iterator ends when the free bytes
+/// of the strings are exhausted, which only happens on 32-bit targets, after
+/// 2^32 strings of two words.
+///
+/// # Panics
+///
+/// Panics if `len` is not a multiple of the size of a `usize` of at least two
+/// words, since the last word would be hashed in several pieces.
+///
+/// # Example
+///
+/// ```rust
+/// use rustc_hash::{collisions, fx_hash_one, FxHasher};
+///
+/// let mut prefix = FxHasher::default();
+/// prefix.write_usize(32);
+/// for bytes in collisions::byte_strings(&prefix, 42, 32).take(100) {
+///     assert_eq!(fx_hash_one(&bytes), 42);
+/// }
+/// ```
+pub fn byte_strings(prefix: &FxHasher, target: u64, len: usize) -> ByteStrings {
+    check_len(len);
+    let free = len - WORD;
+    ByteStrings {
+        prefix: prefix.clone(),
+        target: target as usize,
+        len,
+        counter: 0,
+        end: 1u64.checked_shl(8 * free as u32),
+    }
+}
+
+/// Returns an iterator over distinct strings of `len` printable ASCII
+/// characters that make `prefix` finish with `target` once they are hashed
+/// as a `str`.
+///
+/// This is how `str` and `String` keys are hashed, so the strings collide in
+/// an `FxHashMap<String, V>` when `prefix` is its hasher. The strings start
+/// with letters and digits, and end with a word of printable characters that
+/// is found by trying about 2,800 starts for each string on 64-bit targets.
+///
+/// # Panics
+///
+/// Panics if `len` is not a multiple of the size of a `usize` of at least two
+/// words, since the last word would be hashed in several pieces.
+///
+/// # Example
+///
+/// ```rust
+/// use rustc_hash::{collisions, fx_hash_str, FxHasher};
+///
+/// for s in collisions::strings(&FxHasher::default(), 42, 24).take(100) {
+///     assert!(s.bytes().all(|b| b == b' ' || b.is_ascii_graphic()));
+///     assert_eq!(fx_hash_str(&s), 42);
+/// }
+/// ```
+pub fn strings(prefix: &FxHasher, target: u64, len: usize) -> Strings {
+    check_len(len);
+    let free = len - WORD;
+    Strings {
+        prefix: prefix.clone(),
+        // `str` writes `0xff` after its bytes.
+        target: unsolve(target as usize, 0xff),
+        len,
+        counter: 0,
+        end: (DIGITS.len() as u64).checked_pow(free as u32),
+    }
+}
+
+fn check_len(len: usize) {
+    assert!(
+        len % WORD == 0 && len >= 2 * WORD,
+        "the length must be a multiple of {} bytes of at least {} bytes",
+        WORD,
+        2 * WORD
+    );
+}
+
+/// An iterator over sequences of integers with the same hash, see
+/// [`u64_sequences`].
+#[derive(Clone, Debug)]
+pub struct U64Sequences {
+    prefix: FxHasher,
+    target: usize,
+    len: usize,
+    counter: u64,
+}
+
+impl Iterator for U64Sequences {
+    type Item = Vec<u64>;
+
+    fn next(&mut self) -> Option<Vec<u64>> {
+        let mut seq = std::vec![0; self.len];
+        seq[0] = self.counter;
+        self.counter = self.counter.wrapping_add(1);
+
+        let mut hasher = self.prefix.clone();
+        for &i in &seq[..self.len - 1] {
+            hasher.write_u64(i);
+        }
+        seq[self.len - 1] = last_u64(&hasher, self.target);
+        Some(seq)
+    }
+}
+
+/// An iterator over byte strings with the same hash, see [`byte_strings`].
+#[derive(Clone, Debug)]
+pub struct ByteStrings {
+    prefix: FxHasher,
+    target: usize,
+    len: usize,
+    counter: u64,
+    end: Option<u64>,
+}
+
+impl Iterator for ByteStrings {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        if Some(self.counter) == self.end {
+            return None;
+        }
+        let free = self.len - WORD;
+        let mut bytes = std::vec![0; self.len];
+        let counter = self.counter.to_le_bytes();
+        let n = free.min(counter.len());
+        bytes[..n].copy_from_slice(&counter[..n]);
+        self.counter += 1;
+
+        let mut hasher = self.prefix.clone();
+        hasher.write_bytes(&bytes[..free]);
+        let last = solve(hasher.hash, self.target);
+        bytes[free..].copy_from_slice(&last.to_ne_bytes());
+        Some(bytes)
+    }
+}
+
+/// An iterator over printable ASCII strings with the same hash, see
+/// [`strings`].
+#[derive(Clone, Debug)]
+pub struct Strings {
+    prefix: FxHasher,
+    // The state expected before the `0xff` written after the string.
+    target: usize,
+    len: usize,
+    counter: u64,
+    end: Option<u64>,
+}
+
+impl Iterator for Strings {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let free = self.len - WORD;
+        let mut bytes = std::vec![DIGITS[0]; self.len];
+        loop {
+            if Some(self.counter) == self.end {
+                return None;
+            }
+            let mut n = self.counter;
+            for byte in &mut bytes[..free] {
+                *byte = DIGITS[(n % DIGITS.len() as u64) as usize];
+                n /= DIGITS.len() as u64;
+            }
+            self.counter += 1;
+
+            let mut hasher = self.prefix.clone();
+            hasher.write_bytes(&bytes[..free]);
+            let last = solve(hasher.hash, self.target).to_ne_bytes();
+            if last.iter().all(|&b| b == b' ' || b.is_ascii_graphic()) {
+                bytes[free..].copy_from_slice(&last);
+                return Some(String::from_utf8(bytes).unwrap());
+            }
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use core::convert::TryInto;
+use core::hash::Hasher;
+use core::ops::BitXor;
+
+const K32: u32 = 0x9e3779b9;
+const K64: u64 = 0x517cc1b727220a95;
+
+/// The 64-bit `fx` algorithm, producing the same hashes on every target.
+///
+/// `FxHasher` works on `usize` words, picks its multiplier by pointer width
+/// and reads bytes in native endianness, so its hashes differ between 32- and
+/// 64-bit targets and between little- and big-endian ones. `FxHasher64`
+/// always keeps a `u64` state, reads bytes as little-endian and hashes `usize`
+/// and `isize` as 64-bit values. On 64-bit little-endian targets it produces
+/// exactly the same hashes as `FxHasher`.
+///
+/// # Example
+///
+/// ```rust
+/// use std::hash::{Hash, Hasher};
+/// use rustc_hash::{FxHasher, FxHasher64};
+///
+/// fn fx64(f: impl Fn(&mut FxHasher64)) -> u64 {
+///     let mut hasher = FxHasher64::default();
+///     f(&mut hasher);
+///     hasher.finish()
+/// }
+///
+/// // These hold on every host, whatever its pointer width or endianness.
+/// assert_eq!(fx64(|h| h.write_u64(0x0123456789abcdef)), 0x56cc4aad99c8321b);
+/// assert_eq!(
+///     fx64(|h| h.write_u128(0x0123456789abcdef_fedcba9876543210)),
+///     0x9b6753504669aa49
+/// );
+/// assert_eq!(fx64(|h| h.write_usize(12345)), 0x8919791e18904b2d);
+/// assert_eq!(fx64(|h| h.write_isize(-1)), 0xae833e48d8ddf56b);
+/// assert_eq!(fx64(|h| h.write(b"hello world!")), 0x1a4f0af733454230);
+/// assert_eq!(fx64(|h| h.write(&[1, 2, 3, 4, 5, 6, 7])), 0xb72b4fe2838adfe1);
+/// assert_eq!(fx64(|h| "rustc".hash(h)), 0xe6dfb73002edbb7d);
+///
+/// if cfg!(all(target_pointer_width = "64", target_endian = "little")) {
+///     let mut hasher = FxHasher::default();
+///     "rustc".hash(&mut hasher);
+///     assert_eq!(hasher.finish(), 0xe6dfb73002edbb7d);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct FxHasher64 {
+    hash: u64,
+}
+
+impl Default for FxHasher64 {
+    #[inline]
+    fn default() -> FxHasher64 {
+        FxHasher64 { hash: 0 }
+    }
+}
+
+impl FxHasher64 {
+    /// Creates a `FxHasher64` whose state starts from `seed` instead of zero.
+    #[inline]
+    pub fn with_seed(seed: u64) -> FxHasher64 {
+        FxHasher64 { hash: seed }
+    }
+
+    #[inline]
+    fn add_to_hash(&mut self, i: u64) {
+        self.hash = self.hash.rotate_left(5).bitxor(i).wrapping_mul(K64);
+    }
+}
+
+impl Hasher for FxHasher64 {
+    #[inline]
+    fn write(&mut self, mut bytes: &[u8]) {
+        let mut hash = FxHasher64 { hash: self.hash };
+        while bytes.len() >= 8 {
+            hash.add_to_hash(u64::from_le_bytes(bytes[..8].try_into().unwrap()));
+            bytes = &bytes[8..];
+        }
+        if bytes.len() >= 4 {
+            hash.add_to_hash(u32::from_le_bytes(bytes[..4].try_into().unwrap()) as u64);
+            bytes = &bytes[4..];
+        }
+        if bytes.len() >= 2 {
+            hash.add_to_hash(u16::from_le_bytes(bytes[..2].try_into().unwrap()) as u64);
+            bytes = &bytes[2..];
+        }
+        if !bytes.is_empty() {
+            hash.add_to_hash(bytes[0] as u64);
+        }
+        self.hash = hash.hash;
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.add_to_hash(i);
+    }
+
+    #[inline]
+    fn write_u128(&mut self, i: u128) {
+        self.add_to_hash(i as u64);
+        self.add_to_hash((i >> 64) as u64);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn write_isize(&mut self, i: isize) {
+        // Sign-extend, so that negative values hash the same on 32-bit targets.
+        self.add_to_hash(i as i64 as u64);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+/// The original 32-bit `fx` algorithm used by Firefox, on every target.
+///
+/// This is the `AddToHash` function from mozilla's `HashFunctions.h`: the
+/// state is rotated left by 5 bits, xor-ed with the next 32-bit value and
+/// multiplied by the golden ratio constant `0x9e3779b9`. It is what
+/// `FxHasher` computes on 32-bit targets, except that bytes are always read
+/// as little-endian. The 32-bit hash is returned by `finish32`.
+///
+/// Like Firefox, 64-bit values are added as their low and then their high
+/// half, and `usize` values are added like `u32` or `u64` depending on the
+/// pointer width of the target. Note that Firefox's `HashString` adds one
+/// character at a time, which corresponds to calling `write_u8` (or
+/// `write_u16` for UTF-16) for each of them rather than `write`.
+///
+/// # Example
+///
+/// ```rust
+/// use std::hash::Hasher;
+/// use rustc_hash::FxHasher32;
+///
+/// fn fx32(f: impl Fn(&mut FxHasher32)) -> u32 {
+///     let mut hasher = FxHasher32::default();
+///     f(&mut hasher);
+///     hasher.finish32()
+/// }
+///
+/// // `AddToHash(0, uint32_t(0x12345678))`
+/// assert_eq!(fx32(|h| h.write_u32(0x12345678)), 0x887934b8);
+/// // `AddToHash(0, uint64_t(0x0123456789abcdef))`
+/// assert_eq!(fx32(|h| h.write_u64(0x0123456789abcdef)), 0x29d11f01);
+/// // `AddToHash(0, 1, 2)`
+/// assert_eq!(fx32(|h| { h.write_u32(1); h.write_u32(2) }), 0xed7c0b69);
+/// // `HashString("hello")`
+/// assert_eq!(fx32(|h| b"hello".iter().for_each(|&c| h.write_u8(c))), 0x0cdf45db);
+///
+/// assert_eq!(fx32(|h| h.write(b"hello world!")), 0x128d0c12);
+/// ```
+#[derive(Clone, Debug)]
+pub struct FxHasher32 {
+    hash: u32,
+}
+
+impl Default for FxHasher32 {
+    #[inline]
+    fn default() -> FxHasher32 {
+        FxHasher32 { hash: 0 }
+    }
+}
+
+impl FxHasher32 {
+    /// Creates a `FxHasher32` whose state starts from `seed` instead of zero.
+    #[inline]
+    pub fn with_seed(seed: u32) -> FxHasher32 {
+        FxHasher32 { hash: seed }
+    }
+
+    /// Returns the 32-bit hash value for the values written so far.
+    #[inline]
+    pub fn finish32(&self) -> u32 {
+        self.hash
+    }
+
+    #[inline]
+    fn add_to_hash(&mut self, i: u32) {
+        self.hash = self.hash.rotate_left(5).bitxor(i).wrapping_mul(K32);
+    }
+}
+
+impl Hasher for FxHasher32 {
+    #[inline]
+    fn write(&mut self, mut bytes: &[u8]) {
+        let mut hash = FxHasher32 { hash: self.hash };
+        while bytes.len() >= 4 {
+            hash.add_to_hash(u32::from_le_bytes(bytes[..4].try_into().unwrap()));
+            bytes = &bytes[4..];
+        }
+        if bytes.len() >= 2 {
+            hash.add_to_hash(u16::from_le_bytes(bytes[..2].try_into().unwrap()) as u32);
+            bytes = &bytes[2..];
+        }
+        if !bytes.is_empty() {
+            hash.add_to_hash(bytes[0] as u32);
+        }
+        self.hash = hash.hash;
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.add_to_hash(i as u32);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.add_to_hash(i as u32);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.add_to_hash(i);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.add_to_hash(i as u32);
+        self.add_to_hash((i >> 32) as u32);
+    }
+
+    #[inline]
+    fn write_u128(&mut self, i: u128) {
+        self.write_u64(i as u64);
+        self.write_u64((i >> 64) as u64);
+    }
+
+    #[cfg(target_pointer_width = "32")]
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.add_to_hash(i as u32);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash as u64
+    }
+}
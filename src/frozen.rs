@@ -0,0 +1,356 @@
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::Hash;
+use core::iter::FromIterator;
+use core::ops::Index;
+use core::slice;
+
+#[cfg(all(not(feature = "std"), feature = "hashbrown"))]
+use hashbrown::hash_map;
+#[cfg(feature = "std")]
+use std::collections::hash_map;
+
+use crate::phf::{displace, split, SeededHasher};
+use crate::FxHashMap;
+
+// The average number of keys per bucket of displacements.
+const KEYS_PER_BUCKET: usize = 4;
+
+// The number of seeds tried before falling back to a hash table.
+const MAX_SEEDS: u64 = 16;
+
+// The displacements of each bucket, and the index of the entry in each slot.
+type Placement = (Vec<(u32, u32)>, Vec<usize>);
+
+#[inline]
+fn hashes<Q: Hash + ?Sized>(seed: u64, key: &Q) -> (u32, u32, u32) {
+    let mut hasher = SeededHasher::new(seed);
+    key.hash(&mut hasher);
+    split(hasher.finish())
+}
+
+/// An immutable map using a minimal perfect hash, for maps that are built once
+/// and then only read.
+///
+/// Freezing an `FxHashMap` searches for a seed of a seeded hash and for a
+/// pair of displacements for each bucket of about four keys, so that every
+/// key is sent to its own slot in a dense array of entries (the "hash,
+/// displace and compress" scheme). A lookup is then one hash and at most one
+/// key comparison, and the map only uses about two bytes per entry on top of
+/// the entries themselves.
+///
+/// The seed changes every bit of the hash, which is not the same as that of
+/// `FxHasher`, so that a seed for which a few keys collide is followed by one
+/// for which they do not. If no perfect hash is found with any of 16 seeds,
+/// which happens when the `Hash` implementation of two different keys writes
+/// the same values, the map falls back to keeping the `FxHashMap` it was
+/// built from. `is_perfect` tells which representation is used.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(any(feature = "std", feature = "hashbrown"))]
+/// # fn main() {
+/// use rustc_hash::{FrozenFxMap, FxHashMap};
+///
+/// let mut map: FxHashMap<&str, u32> = FxHashMap::default();
+/// for (i, word) in ["fn", "let", "match", "struct", "enum"].iter().enumerate() {
+///     map.insert(*word, i as u32);
+/// }
+///
+/// let frozen = FrozenFxMap::from(map);
+/// assert!(frozen.is_perfect());
+/// assert_eq!(frozen.get("match"), Some(&2));
+/// assert_eq!(frozen.get("impl"), None);
+/// assert_eq!(frozen.len(), 5);
+/// assert_eq!(frozen.displacements().unwrap().len(), 2);
+/// # }
+/// # #[cfg(not(any(feature = "std", feature = "hashbrown")))]
+/// # fn main() { }
+/// ```
+pub struct FrozenFxMap<K, V> {
+    repr: Repr<K, V>,
+}
+
+enum Repr<K, V> {
+    Perfect {
+        seed: u64,
+        displacements: Vec<(u32, u32)>,
+        /// The entries, in the order of their slots.
+        entries: Vec<(K, V)>,
+    },
+    Fallback(FxHashMap<K, V>),
+}
+
+impl<K: Hash + Eq, V> FrozenFxMap<K, V> {
+    fn freeze(map: FxHashMap<K, V>) -> FrozenFxMap<K, V> {
+        let entries: Vec<(K, V)> = map.into_iter().collect();
+        for seed in 0..MAX_SEEDS {
+            if let Some((displacements, slots)) = FrozenFxMap::<K, V>::try_build(&entries, seed) {
+                let mut entries: Vec<Option<(K, V)>> = entries.into_iter().map(Some).collect();
+                let entries = slots.iter().map(|&i| entries[i].take().unwrap()).collect();
+                return FrozenFxMap {
+                    repr: Repr::Perfect {
+                        seed,
+                        displacements,
+                        entries,
+                    },
+                };
+            }
+        }
+        FrozenFxMap {
+            repr: Repr::Fallback(entries.into_iter().collect()),
+        }
+    }
+
+    /// Returns the displacements of each bucket and the index in `entries` of
+    /// the entry stored in each slot, or `None` if `seed` does not work.
+    fn try_build(entries: &[(K, V)], seed: u64) -> Option<Placement> {
+        let len = entries.len();
+        let bucket_count = len.div_ceil(KEYS_PER_BUCKET);
+        let hashes: Vec<_> = entries.iter().map(|(k, _)| hashes(seed, k)).collect();
+        let mut buckets: Vec<Vec<usize>> = (0..bucket_count).map(|_| Vec::new()).collect();
+        for (i, &(bucket, _, _)) in hashes.iter().enumerate() {
+            buckets[bucket as usize % bucket_count].push(i);
+        }
+        let mut order: Vec<usize> = (0..bucket_count).collect();
+        order.sort_by_key(|&b| core::cmp::Reverse(buckets[b].len()));
+
+        let mut displacements = alloc::vec![(0, 0); bucket_count];
+        let mut slots = alloc::vec![usize::MAX; len];
+        let mut candidate = Vec::new();
+        let mut free = 0;
+        for b in order {
+            let bucket = &buckets[b];
+            if bucket.len() == 1 {
+                // A single key can be sent to any free slot with `d1 = 0`.
+                while slots[free] != usize::MAX {
+                    free += 1;
+                }
+                let f1 = hashes[bucket[0]].1 as usize % len;
+                displacements[b] = (0, ((free + len - f1) % len) as u32);
+                slots[free] = bucket[0];
+                continue;
+            }
+            // Keys whose hashes only differ in the bucket are sent to the
+            // same slot by every displacement, so give up on this seed early.
+            for (n, &i) in bucket.iter().enumerate() {
+                let (_, f1, f2) = hashes[i];
+                if bucket[..n].iter().any(|&j| {
+                    let (_, g1, g2) = hashes[j];
+                    (f1 as usize % len, f2 as usize % len) == (g1 as usize % len, g2 as usize % len)
+                }) {
+                    return None;
+                }
+            }
+            let found = (0..len as u32)
+                .flat_map(|d1| (0..len as u32).map(move |d2| (d1, d2)))
+                .find(|&d| {
+                    candidate.clear();
+                    bucket.iter().all(|&i| {
+                        let (_, f1, f2) = hashes[i];
+                        let slot = displace(f1, f2, d, len);
+                        let ok = slots[slot] == usize::MAX && !candidate.contains(&slot);
+                        candidate.push(slot);
+                        ok
+                    })
+                });
+            let d = found?;
+            displacements[b] = d;
+            for (&i, &slot) in bucket.iter().zip(candidate.iter()) {
+                slots[slot] = i;
+            }
+        }
+        Some((displacements, slots))
+    }
+}
+
+impl<K, V> FrozenFxMap<K, V> {
+    /// Returns `true` if the map uses a perfect hash, and `false` if it fell
+    /// back to a hash table.
+    pub fn is_perfect(&self) -> bool {
+        match self.repr {
+            Repr::Perfect { .. } => true,
+            Repr::Fallback(_) => false,
+        }
+    }
+
+    /// Returns the seed of the hash used by the perfect hash.
+    pub fn seed(&self) -> Option<u64> {
+        match self.repr {
+            Repr::Perfect { seed, .. } => Some(seed),
+            Repr::Fallback(_) => None,
+        }
+    }
+
+    /// Returns the displacements `(d1, d2)` of each bucket of the perfect hash.
+    ///
+    /// A key whose hash is split into `bucket`, `f1` and `f2` is stored in
+    /// slot `(f1 + d1 * f2 + d2) % len`, where `(d1, d2)` are the
+    /// displacements of its bucket.
+    pub fn displacements(&self) -> Option<&[(u32, u32)]> {
+        match self.repr {
+            Repr::Perfect {
+                ref displacements, ..
+            } => Some(displacements),
+            Repr::Fallback(_) => None,
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        match self.repr {
+            Repr::Perfect { ref entries, .. } => entries.len(),
+            Repr::Fallback(ref map) => map.len(),
+        }
+    }
+
+    /// Returns `true` if the map contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// An iterator visiting all key-value pairs in arbitrary order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let inner = match self.repr {
+            Repr::Perfect { ref entries, .. } => IterRepr::Perfect(entries.iter()),
+            Repr::Fallback(ref map) => IterRepr::Fallback(map.iter()),
+        };
+        Iter { inner }
+    }
+
+    /// An iterator visiting all keys in arbitrary order.
+    pub fn keys(&self) -> impl Iterator<Item = &K> + '_ {
+        self.iter().map(|(k, _)| k)
+    }
+
+    /// An iterator visiting all values in arbitrary order.
+    pub fn values(&self) -> impl Iterator<Item = &V> + '_ {
+        self.iter().map(|(_, v)| v)
+    }
+
+    /// Returns the key and the value corresponding to `key`.
+    #[inline]
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q> + Hash + Eq,
+        Q: Hash + Eq + ?Sized,
+    {
+        match self.repr {
+            Repr::Perfect {
+                seed,
+                ref displacements,
+                ref entries,
+            } => {
+                if entries.is_empty() {
+                    return None;
+                }
+                let (bucket, f1, f2) = hashes(seed, key);
+                let d = displacements[bucket as usize % displacements.len()];
+                let (k, v) = &entries[displace(f1, f2, d, entries.len())];
+                if k.borrow() == key {
+                    Some((k, v))
+                } else {
+                    None
+                }
+            }
+            Repr::Fallback(ref map) => map.get_key_value(key),
+        }
+    }
+
+    /// Returns a reference to the value corresponding to `key`.
+    #[inline]
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q> + Hash + Eq,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_key_value(key).map(|(_, v)| v)
+    }
+
+    /// Returns `true` if the map contains a value for `key`.
+    #[inline]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q> + Hash + Eq,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_key_value(key).is_some()
+    }
+}
+
+impl<K: Hash + Eq, V> From<FxHashMap<K, V>> for FrozenFxMap<K, V> {
+    fn from(map: FxHashMap<K, V>) -> FrozenFxMap<K, V> {
+        FrozenFxMap::freeze(map)
+    }
+}
+
+impl<K: Hash + Eq, V> FromIterator<(K, V)> for FrozenFxMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> FrozenFxMap<K, V> {
+        FrozenFxMap::freeze(iter.into_iter().collect())
+    }
+}
+
+impl<K, Q, V> Index<&Q> for FrozenFxMap<K, V>
+where
+    K: Borrow<Q> + Hash + Eq,
+    Q: Hash + Eq + ?Sized,
+{
+    type Output = V;
+
+    /// Returns a reference to the value corresponding to `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is not present in the map.
+    #[inline]
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found in FrozenFxMap")
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for FrozenFxMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a FrozenFxMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+/// An iterator over the entries of a `FrozenFxMap`.
+pub struct Iter<'a, K, V> {
+    inner: IterRepr<'a, K, V>,
+}
+
+enum IterRepr<'a, K, V> {
+    Perfect(slice::Iter<'a, (K, V)>),
+    Fallback(hash_map::Iter<'a, K, V>),
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    #[inline]
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        match self.inner {
+            IterRepr::Perfect(ref mut iter) => iter.next().map(|(k, v)| (k, v)),
+            IterRepr::Fallback(ref mut iter) => iter.next(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.inner {
+            IterRepr::Perfect(ref iter) => iter.size_hint(),
+            IterRepr::Fallback(ref iter) => iter.size_hint(),
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use crate::FxHasher;
+
+/// A value that can be fed directly to an `FxHasher`.
+///
+/// This is the counterpart of `Hash` for `FxHasher` only: implementations
+/// call the inherent methods of `FxHasher` without going through a generic
+/// `Hasher`, and do not need to follow the layout of `Hash`. With the
+/// `derive` feature, `#[derive(FxHash)]` packs the small fields of a struct
+/// and the discriminant of an enum into as few words as possible, instead of
+/// writing each of them separately as `#[derive(Hash)]` does.
+///
+/// `#[fx_hash(bridge)]` also derives a `Hash` implementation writing the
+/// `FxHash` digest of the value, so that the type can be used as the key of
+/// an `FxHashMap`.
+///
+/// # Example
+///
+/// The derived implementation packs the fields of this key into one word
+/// that is mixed bijectively before being written. On 64-bit targets,
+/// distinct keys therefore never have the same hash; on 32-bit targets the
+/// word is added to a 32-bit state as two halves, and they may. The bucket
+/// indices of a table with as many buckets as keys collide about as often as
+/// random ones would, and less often than with `#[derive(Hash)]`.
+/// `examples/bench_derive.rs` also compares their speed.
+///
+/// ```rust
+/// # #[cfg(all(feature = "std", feature = "derive"))]
+/// # fn main() {
+/// use rustc_hash::{fx_hash_one, FxHash, FxHashSet, FxHasher};
+///
+/// #[derive(FxHash)]
+/// struct Packed {
+///     kind: u8,
+///     flags: u8,
+///     index: u32,
+/// }
+///
+/// #[derive(Hash)]
+/// struct Derived {
+///     kind: u8,
+///     flags: u8,
+///     index: u32,
+/// }
+///
+/// let (mut packed, mut derived) = (Vec::new(), Vec::new());
+/// for kind in 0..16 {
+///     for flags in 0..64 {
+///         for index in 0..64 {
+///             let mut hasher = FxHasher::default();
+///             Packed { kind, flags, index }.fx_hash(&mut hasher);
+///             packed.push(hasher.finish());
+///             derived.push(fx_hash_one(&Derived { kind, flags, index }));
+///         }
+///     }
+/// }
+///
+/// let collisions = |hashes: &[u64], mask: u64| {
+///     let set: FxHashSet<u64> = hashes.iter().map(|h| h & mask).collect();
+///     hashes.len() - set.len()
+/// };
+/// if cfg!(target_pointer_width = "64") {
+///     assert_eq!(collisions(&packed, !0), 0);
+/// }
+/// // Random hashes would give about 24_100 collisions.
+/// assert!(collisions(&packed, 0xffff) < 25_000);
+/// assert!(collisions(&packed, 0xffff) < collisions(&derived, 0xffff));
+/// # }
+/// # #[cfg(not(all(feature = "std", feature = "derive")))]
+/// # fn main() { }
+/// ```
+///
+/// With `#[fx_hash(bridge)]`, the type can be used as a key:
+///
+/// ```rust
+/// # #[cfg(all(feature = "std", feature = "derive"))]
+/// # fn main() {
+/// use rustc_hash::{FxHash, FxHashMap};
+///
+/// #[derive(PartialEq, Eq, FxHash)]
+/// #[fx_hash(bridge)]
+/// enum Ty<T> {
+///     Int { signed: bool, bits: u8 },
+///     Adt(u32, Vec<T>),
+/// }
+///
+/// let mut map = FxHashMap::default();
+/// map.insert(Ty::<u32>::Int { signed: true, bits: 32 }, "i32");
+/// map.insert(Ty::Adt(7, vec![1, 2]), "Vec<u32>");
+/// assert_eq!(map[&Ty::Int { signed: true, bits: 32 }], "i32");
+/// assert_eq!(map.get(&Ty::Adt(7, vec![1])), None);
+/// # }
+/// # #[cfg(not(all(feature = "std", feature = "derive")))]
+/// # fn main() { }
+/// ```
+pub trait FxHash {
+    /// Feeds this value into the given `FxHasher`.
+    fn fx_hash(&self, hasher: &mut FxHasher);
+
+    /// Feeds a slice of this type into the given `FxHasher`.
+    ///
+    /// This does not include the length of the slice, which is written by
+    /// the implementation for `[T]`.
+    #[inline]
+    fn fx_hash_slice(data: &[Self], hasher: &mut FxHasher)
+    where
+        Self: Sized,
+    {
+        for value in data {
+            value.fx_hash(hasher);
+        }
+    }
+}
+
+/// Writes a word of fields packed by `#[derive(FxHash)]`.
+///
+/// A single `add_to_hash` leaves the low bits of the hash, from which tables
+/// take the bucket index, depending only on the low bits of the word. The word
+/// is first multiplied by an odd constant, which carries every field into the
+/// high half, and the high half is then xor-ed into the low one with
+/// `word ^ (word >> 32)`. Both steps are invertible, so distinct words are
+/// still distinct when they are passed to `write_u64`.
+#[doc(hidden)]
+#[inline]
+pub fn __write_packed(hasher: &mut FxHasher, mut word: u64) {
+    word = word.wrapping_mul(0xf1357aea2e62a9c5);
+    word ^= word >> 32;
+    hasher.write_u64(word);
+}
+
+macro_rules! impl_fx_hash_int {
+    ($($ty:ty => $write:ident as $as:ty,)*) => {
+        $(
+            impl FxHash for $ty {
+                #[inline]
+                fn fx_hash(&self, hasher: &mut FxHasher) {
+                    hasher.$write(*self as $as);
+                }
+            }
+        )*
+    };
+}
+
+impl_fx_hash_int! {
+    i8 => write_u8 as u8,
+    u16 => write_u16 as u16,
+    i16 => write_u16 as u16,
+    u32 => write_u32 as u32,
+    i32 => write_u32 as u32,
+    u64 => write_u64 as u64,
+    i64 => write_u64 as u64,
+    u128 => write_u128 as u128,
+    i128 => write_u128 as u128,
+    usize => write_usize as usize,
+    isize => write_usize as usize,
+    bool => write_u8 as u8,
+    char => write_u32 as u32,
+}
+
+impl FxHash for u8 {
+    #[inline]
+    fn fx_hash(&self, hasher: &mut FxHasher) {
+        hasher.write_u8(*self);
+    }
+
+    #[inline]
+    fn fx_hash_slice(data: &[u8], hasher: &mut FxHasher) {
+        hasher.write_bytes(data);
+    }
+}
+
+impl FxHash for () {
+    #[inline]
+    fn fx_hash(&self, _hasher: &mut FxHasher) {}
+}
+
+impl FxHash for str {
+    #[inline]
+    fn fx_hash(&self, hasher: &mut FxHasher) {
+        hasher.write_bytes(self.as_bytes());
+        hasher.write_u8(0xff);
+    }
+}
+
+impl<T: FxHash> FxHash for [T] {
+    #[inline]
+    fn fx_hash(&self, hasher: &mut FxHasher) {
+        hasher.write_usize(self.len());
+        T::fx_hash_slice(self, hasher);
+    }
+}
+
+impl<T: FxHash, const N: usize> FxHash for [T; N] {
+    #[inline]
+    fn fx_hash(&self, hasher: &mut FxHasher) {
+        T::fx_hash_slice(self, hasher);
+    }
+}
+
+impl<T: FxHash + ?Sized> FxHash for &T {
+    #[inline]
+    fn fx_hash(&self, hasher: &mut FxHasher) {
+        (**self).fx_hash(hasher);
+    }
+}
+
+impl<T: FxHash> FxHash for Option<T> {
+    #[inline]
+    fn fx_hash(&self, hasher: &mut FxHasher) {
+        match *self {
+            None => hasher.write_u8(0),
+            Some(ref value) => {
+                hasher.write_u8(1);
+                value.fx_hash(hasher);
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl FxHash for alloc::string::String {
+    #[inline]
+    fn fx_hash(&self, hasher: &mut FxHasher) {
+        self.as_str().fx_hash(hasher);
+    }
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<T: FxHash> FxHash for alloc::vec::Vec<T> {
+    #[inline]
+    fn fx_hash(&self, hasher: &mut FxHasher) {
+        self[..].fx_hash(hasher);
+    }
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<T: FxHash + ?Sized> FxHash for alloc::boxed::Box<T> {
+    #[inline]
+    fn fx_hash(&self, hasher: &mut FxHasher) {
+        (**self).fx_hash(hasher);
+    }
+}
+
+macro_rules! impl_fx_hash_tuple {
+    ($(($($name:ident)+),)*) => {
+        $(
+            impl<$($name: FxHash),+> FxHash for ($($name,)+) {
+                #[inline]
+                #[allow(non_snake_case)]
+                fn fx_hash(&self, hasher: &mut FxHasher) {
+                    let ($(ref $name,)+) = *self;
+                    $($name.fx_hash(hasher);)+
+                }
+            }
+        )*
+    };
+}
+
+impl_fx_hash_tuple! {
+    (A),
+    (A B),
+    (A B C),
+    (A B C D),
+    (A B C D E),
+    (A B C D E F),
+    (A B C D E F G),
+    (A B C D E F G H),
+}
@@ -0,0 +1,132 @@
+use core::hash::BuildHasher;
+#[cfg(not(target_has_atomic = "64"))]
+use core::sync::atomic::AtomicU32;
+#[cfg(target_has_atomic = "64")]
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
+
+use crate::mix::mix_seed;
+use crate::v1::FxHasher;
+
+#[cfg(target_has_atomic = "64")]
+static GLOBAL_SEED: AtomicU64 = AtomicU64::new(0);
+
+// Without 64-bit atomics, the seed is stored as its low and its high half.
+#[cfg(not(target_has_atomic = "64"))]
+static GLOBAL_SEED: [AtomicU32; 2] = [AtomicU32::new(0), AtomicU32::new(0)];
+
+/// Sets the process-wide seed used by the `FxGlobalSeedState`s created from
+/// now on.
+///
+/// States that were already created, and so the maps using them, keep the
+/// seed they were created with. All 64 bits of the seed are kept, and change
+/// the iteration order, on every target. On targets without 64-bit atomics,
+/// the two halves of the seed are stored separately, so a state created while
+/// another thread sets the seed may get one half of each seed.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "std")]
+/// # fn main() {
+/// use rustc_hash::{global_seed, set_global_seed, FxGlobalSeedHashMap};
+///
+/// fn run() -> Vec<u32> {
+///     let map: FxGlobalSeedHashMap<u32, ()> = (0..16).map(|i| (i, ())).collect();
+///     map.keys().cloned().collect()
+/// }
+///
+/// // Running the same program with the same seed gives the same order.
+/// set_global_seed(7);
+/// let first = run();
+/// set_global_seed(7);
+/// assert_eq!(run(), first);
+///
+/// // Sweeping seeds shows whether it depends on the iteration order.
+/// let orders: Vec<Vec<u32>> = (0..8)
+///     .map(|seed| {
+///         set_global_seed(seed);
+///         run()
+///     })
+///     .collect();
+/// assert!(orders.iter().any(|order| *order != first));
+///
+/// // The high bits of the seed count as well.
+/// set_global_seed(1 << 32);
+/// assert_eq!(global_seed(), 1 << 32);
+/// assert_ne!(run(), orders[0]);
+/// # }
+/// # #[cfg(not(feature = "std"))]
+/// # fn main() { }
+/// ```
+#[inline]
+pub fn set_global_seed(seed: u64) {
+    #[cfg(target_has_atomic = "64")]
+    GLOBAL_SEED.store(seed, Ordering::Relaxed);
+    #[cfg(not(target_has_atomic = "64"))]
+    {
+        GLOBAL_SEED[0].store(seed as u32, Ordering::Relaxed);
+        GLOBAL_SEED[1].store((seed >> 32) as u32, Ordering::Relaxed);
+    }
+}
+
+/// Returns the process-wide seed set by `set_global_seed`, zero by default.
+#[inline]
+pub fn global_seed() -> u64 {
+    #[cfg(target_has_atomic = "64")]
+    return GLOBAL_SEED.load(Ordering::Relaxed);
+    #[cfg(not(target_has_atomic = "64"))]
+    {
+        let low = GLOBAL_SEED[0].load(Ordering::Relaxed) as u64;
+        let high = GLOBAL_SEED[1].load(Ordering::Relaxed) as u64;
+        high << 32 | low
+    }
+}
+
+/// A `BuildHasher` creating `FxHasher`s that start from the process-wide
+/// seed, as set by `set_global_seed`.
+///
+/// The seed is read when the state is created, so a test harness can run a
+/// whole program under different seeds without passing a seed to every map.
+/// It is mixed before being used, so that consecutive seeds give unrelated
+/// iteration orders. With the default seed of zero, the hashers are
+/// `FxHasher::default()`.
+#[derive(Clone, Copy, Debug)]
+pub struct FxGlobalSeedState {
+    seed: u64,
+    state: u64,
+}
+
+impl FxGlobalSeedState {
+    /// Creates a new `FxGlobalSeedState` with the current process-wide seed.
+    #[inline]
+    pub fn new() -> FxGlobalSeedState {
+        let seed = global_seed();
+        FxGlobalSeedState {
+            seed,
+            state: mix_seed(seed),
+        }
+    }
+
+    /// Returns the seed used by the hashers built from this state.
+    #[inline]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl Default for FxGlobalSeedState {
+    #[inline]
+    fn default() -> FxGlobalSeedState {
+        FxGlobalSeedState::new()
+    }
+}
+
+impl BuildHasher for FxGlobalSeedState {
+    type Hasher = FxHasher;
+
+    #[inline]
+    fn build_hasher(&self) -> FxHasher {
+        FxHasher::with_seed(self.state)
+    }
+}
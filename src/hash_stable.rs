@@ -0,0 +1,296 @@
+use core::hash::{Hash, Hasher};
+
+use crate::{Fingerprint, StableHasher};
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+use crate::{HashMap, HashSet};
+
+/// A value that can be hashed with a `StableHasher`, given a context.
+///
+/// Unlike `Hash`, the implementation receives a context, so it can for
+/// instance hash an interned identifier by the string it refers to rather
+/// than by its index, which depends on the order of interning, or skip data
+/// such as source positions that should not invalidate a cached result.
+///
+/// The implementations for the types of this crate and the standard library
+/// are generic over the context. Maps and sets are hashed in a way that does
+/// not depend on their iteration order, by combining the fingerprints of
+/// their entries with `Fingerprint::combine_commutative`.
+///
+/// With the `derive` feature, `#[derive(HashStable)]` implements this trait
+/// for any context for which the fields implement it, or for a single context
+/// given with `#[stable_hasher(context = Ctx)]`. Fields marked with
+/// `#[stable_hasher(ignore)]` are not hashed.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "std")]
+/// # fn main() {
+/// use rustc_hash::{FxHashMap, HashStable, StableHasher};
+///
+/// struct Interner {
+///     strings: Vec<&'static str>,
+/// }
+///
+/// #[derive(Clone, Copy, PartialEq, Eq, Hash)]
+/// struct Symbol(u32);
+///
+/// impl HashStable<Interner> for Symbol {
+///     fn hash_stable(&self, interner: &mut Interner, hasher: &mut StableHasher) {
+///         let string = interner.strings[self.0 as usize];
+///         string.hash_stable(interner, hasher);
+///     }
+/// }
+///
+/// fn fingerprint(map: &FxHashMap<Symbol, u32>, interner: &mut Interner) -> (u64, u64) {
+///     let mut hasher = StableHasher::new();
+///     map.hash_stable(interner, &mut hasher);
+///     hasher.finish128().as_value()
+/// }
+///
+/// // The same strings, interned in a different order.
+/// let mut a = Interner { strings: vec!["foo", "bar"] };
+/// let mut b = Interner { strings: vec!["bar", "foo"] };
+///
+/// let mut map_a = FxHashMap::default();
+/// map_a.insert(Symbol(0), 1);
+/// map_a.insert(Symbol(1), 2);
+/// let mut map_b = FxHashMap::default();
+/// map_b.insert(Symbol(1), 1);
+/// map_b.insert(Symbol(0), 2);
+///
+/// assert_eq!(fingerprint(&map_a, &mut a), fingerprint(&map_b, &mut b));
+/// # }
+/// # #[cfg(not(feature = "std"))]
+/// # fn main() { }
+/// ```
+///
+/// Deriving the trait, here for a type whose spans should not change its
+/// fingerprint:
+///
+/// ```rust
+/// # #[cfg(all(feature = "std", feature = "derive"))]
+/// # fn main() {
+/// use rustc_hash::{HashStable, StableHasher};
+///
+/// #[derive(HashStable)]
+/// struct Span {
+///     lo: u32,
+///     hi: u32,
+/// }
+///
+/// #[derive(HashStable)]
+/// enum Item<T> {
+///     Const(String, T),
+///     Fn {
+///         name: String,
+///         #[stable_hasher(ignore)]
+///         span: Span,
+///     },
+/// }
+///
+/// fn fingerprint<T: HashStable<()>>(item: &Item<T>) -> (u64, u64) {
+///     let mut hasher = StableHasher::new();
+///     item.hash_stable(&mut (), &mut hasher);
+///     hasher.finish128().as_value()
+/// }
+///
+/// let a: Item<u32> = Item::Fn { name: "main".to_string(), span: Span { lo: 0, hi: 10 } };
+/// let b: Item<u32> = Item::Fn { name: "main".to_string(), span: Span { lo: 4, hi: 14 } };
+/// let c: Item<u32> = Item::Const("main".to_string(), 0);
+/// assert_eq!(fingerprint(&a), fingerprint(&b));
+/// assert_ne!(fingerprint(&a), fingerprint(&c));
+/// # }
+/// # #[cfg(not(all(feature = "std", feature = "derive")))]
+/// # fn main() { }
+/// ```
+pub trait HashStable<Ctx: ?Sized> {
+    /// Feeds this value into the given `StableHasher`.
+    fn hash_stable(&self, ctx: &mut Ctx, hasher: &mut StableHasher);
+}
+
+macro_rules! impl_hash_stable_via_hash {
+    ($($ty:ty,)*) => {
+        $(
+            impl<Ctx: ?Sized> HashStable<Ctx> for $ty {
+                #[inline]
+                fn hash_stable(&self, _ctx: &mut Ctx, hasher: &mut StableHasher) {
+                    self.hash(hasher);
+                }
+            }
+        )*
+    };
+}
+
+impl_hash_stable_via_hash! {
+    u8, u16, u32, u64, u128, usize,
+    i8, i16, i32, i64, i128, isize,
+    bool, char, str, (),
+}
+
+impl<Ctx: ?Sized> HashStable<Ctx> for f32 {
+    #[inline]
+    fn hash_stable(&self, _ctx: &mut Ctx, hasher: &mut StableHasher) {
+        hasher.write_u32(self.to_bits());
+    }
+}
+
+impl<Ctx: ?Sized> HashStable<Ctx> for f64 {
+    #[inline]
+    fn hash_stable(&self, _ctx: &mut Ctx, hasher: &mut StableHasher) {
+        hasher.write_u64(self.to_bits());
+    }
+}
+
+impl<Ctx: ?Sized> HashStable<Ctx> for Fingerprint {
+    #[inline]
+    fn hash_stable(&self, _ctx: &mut Ctx, hasher: &mut StableHasher) {
+        hasher.write_u64(self.0);
+        hasher.write_u64(self.1);
+    }
+}
+
+impl<Ctx: ?Sized, T: HashStable<Ctx>> HashStable<Ctx> for [T] {
+    #[inline]
+    fn hash_stable(&self, ctx: &mut Ctx, hasher: &mut StableHasher) {
+        hasher.write_usize(self.len());
+        for value in self {
+            value.hash_stable(ctx, hasher);
+        }
+    }
+}
+
+impl<Ctx: ?Sized, T: HashStable<Ctx>, const N: usize> HashStable<Ctx> for [T; N] {
+    #[inline]
+    fn hash_stable(&self, ctx: &mut Ctx, hasher: &mut StableHasher) {
+        self[..].hash_stable(ctx, hasher);
+    }
+}
+
+impl<Ctx: ?Sized, T: HashStable<Ctx> + ?Sized> HashStable<Ctx> for &T {
+    #[inline]
+    fn hash_stable(&self, ctx: &mut Ctx, hasher: &mut StableHasher) {
+        (**self).hash_stable(ctx, hasher);
+    }
+}
+
+impl<Ctx: ?Sized, T: HashStable<Ctx>> HashStable<Ctx> for Option<T> {
+    #[inline]
+    fn hash_stable(&self, ctx: &mut Ctx, hasher: &mut StableHasher) {
+        match *self {
+            None => hasher.write_u8(0),
+            Some(ref value) => {
+                hasher.write_u8(1);
+                value.hash_stable(ctx, hasher);
+            }
+        }
+    }
+}
+
+impl<Ctx: ?Sized, T: HashStable<Ctx>, E: HashStable<Ctx>> HashStable<Ctx> for Result<T, E> {
+    #[inline]
+    fn hash_stable(&self, ctx: &mut Ctx, hasher: &mut StableHasher) {
+        match *self {
+            Ok(ref value) => {
+                hasher.write_u8(0);
+                value.hash_stable(ctx, hasher);
+            }
+            Err(ref error) => {
+                hasher.write_u8(1);
+                error.hash_stable(ctx, hasher);
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<Ctx: ?Sized> HashStable<Ctx> for alloc::string::String {
+    #[inline]
+    fn hash_stable(&self, ctx: &mut Ctx, hasher: &mut StableHasher) {
+        self.as_str().hash_stable(ctx, hasher);
+    }
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<Ctx: ?Sized, T: HashStable<Ctx>> HashStable<Ctx> for alloc::vec::Vec<T> {
+    #[inline]
+    fn hash_stable(&self, ctx: &mut Ctx, hasher: &mut StableHasher) {
+        self[..].hash_stable(ctx, hasher);
+    }
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<Ctx: ?Sized, T: HashStable<Ctx> + ?Sized> HashStable<Ctx> for alloc::boxed::Box<T> {
+    #[inline]
+    fn hash_stable(&self, ctx: &mut Ctx, hasher: &mut StableHasher) {
+        (**self).hash_stable(ctx, hasher);
+    }
+}
+
+/// Hashes the elements of a collection in a way that does not depend on the
+/// order in which they are visited.
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+fn hash_stable_unordered<Ctx, T, I>(ctx: &mut Ctx, hasher: &mut StableHasher, len: usize, iter: I)
+where
+    Ctx: ?Sized,
+    T: HashStable<Ctx>,
+    I: Iterator<Item = T>,
+{
+    let mut sum = Fingerprint::ZERO;
+    for value in iter {
+        let mut value_hasher = StableHasher::new();
+        value.hash_stable(ctx, &mut value_hasher);
+        sum = sum.combine_commutative(value_hasher.finish128());
+    }
+    hasher.write_usize(len);
+    sum.hash_stable(ctx, hasher);
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<Ctx, K, V, S> HashStable<Ctx> for HashMap<K, V, S>
+where
+    Ctx: ?Sized,
+    K: HashStable<Ctx>,
+    V: HashStable<Ctx>,
+{
+    fn hash_stable(&self, ctx: &mut Ctx, hasher: &mut StableHasher) {
+        hash_stable_unordered(ctx, hasher, self.len(), self.iter());
+    }
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<Ctx, T, S> HashStable<Ctx> for HashSet<T, S>
+where
+    Ctx: ?Sized,
+    T: HashStable<Ctx>,
+{
+    fn hash_stable(&self, ctx: &mut Ctx, hasher: &mut StableHasher) {
+        hash_stable_unordered(ctx, hasher, self.len(), self.iter());
+    }
+}
+
+macro_rules! impl_hash_stable_tuple {
+    ($(($($name:ident)+),)*) => {
+        $(
+            impl<Ctx: ?Sized, $($name: HashStable<Ctx>),+> HashStable<Ctx> for ($($name,)+) {
+                #[inline]
+                #[allow(non_snake_case)]
+                fn hash_stable(&self, ctx: &mut Ctx, hasher: &mut StableHasher) {
+                    let ($(ref $name,)+) = *self;
+                    $($name.hash_stable(ctx, hasher);)+
+                }
+            }
+        )*
+    };
+}
+
+impl_hash_stable_tuple! {
+    (A),
+    (A B),
+    (A B C),
+    (A B C D),
+    (A B C D E),
+    (A B C D E F),
+    (A B C D E F G),
+    (A B C D E F G H),
+}
@@ -0,0 +1,152 @@
+use core::hash::Hasher;
+use core::mem::size_of;
+
+use crate::v1::{read_u16, read_u32, read_usize, FxHasher};
+
+/// A variant of `FxHasher` whose `write` keeps track of how many bytes were
+/// left over after the last whole word.
+///
+/// `FxHasher::write` folds the trailing 4, 2 and 1 byte chunks of a buffer
+/// into the hash as if they were whole words, so `write(&[1, 0])` and
+/// `write(&[1])` produce the same hash on little-endian targets. That does not
+/// matter for `Hash` implementations, which prefix slices with their length,
+/// but it does for code calling `Hasher::write` directly on raw buffers.
+///
+/// A default `FxHasher` also starts from a zero state, which a zero word
+/// leaves at zero, so `write(&[])`, `write(&[0; 8])` and `write(&[0; 16])`
+/// all produce the same hash.
+///
+/// `FxLengthAwareHasher` instead adds the length of every `write` as a word
+/// before its bytes, and packs the trailing bytes into one extra word. The
+/// integer `write_*` methods behave exactly like those of `FxHasher`.
+///
+/// # Example
+///
+/// ```rust
+/// use std::hash::Hasher;
+/// use rustc_hash::{FxHasher, FxLengthAwareHasher};
+///
+/// fn fx(bytes: &[u8]) -> u64 {
+///     let mut hasher = FxHasher::default();
+///     hasher.write(bytes);
+///     hasher.finish()
+/// }
+///
+/// fn fx_len(bytes: &[u8]) -> u64 {
+///     let mut hasher = FxLengthAwareHasher::default();
+///     hasher.write(bytes);
+///     hasher.finish()
+/// }
+///
+/// let pairs: [(&[u8], &[u8]); 7] = [
+///     (&[1, 0], &[1]),
+///     (&[1, 0, 0, 0], &[1]),
+///     (&[1, 0, 0, 0], &[1, 0]),
+///     (&[1, 0, 0, 0, 0, 0, 0, 0, 2], &[1, 0, 0, 0, 0, 0, 0, 0, 2, 0]),
+///     (&[], &[0; 8]),
+///     (&[], &[0; 16]),
+///     (&[0; 8], &[0; 16]),
+/// ];
+/// for &(a, b) in pairs.iter() {
+///     if cfg!(target_endian = "little") {
+///         assert_eq!(fx(a), fx(b));
+///     }
+///     assert_ne!(fx_len(a), fx_len(b));
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct FxLengthAwareHasher {
+    inner: FxHasher,
+}
+
+impl Default for FxLengthAwareHasher {
+    #[inline]
+    fn default() -> FxLengthAwareHasher {
+        FxLengthAwareHasher {
+            inner: FxHasher::default(),
+        }
+    }
+}
+
+impl From<FxHasher> for FxLengthAwareHasher {
+    /// Continues hashing from the state of `hasher`, for instance one created
+    /// with `FxHasher::with_seed` or `FxHasher::new_nonzero`.
+    #[inline]
+    fn from(hasher: FxHasher) -> FxLengthAwareHasher {
+        FxLengthAwareHasher { inner: hasher }
+    }
+}
+
+impl Hasher for FxLengthAwareHasher {
+    #[inline]
+    fn write(&mut self, mut bytes: &[u8]) {
+        let mut hash = FxHasher {
+            hash: self.inner.hash,
+        };
+        // The length tells apart buffers that only differ by trailing zero
+        // bytes or whole zero words, which would otherwise leave the same
+        // state.
+        hash.add_to_hash(bytes.len());
+        assert!(size_of::<usize>() <= 8);
+        while bytes.len() >= size_of::<usize>() {
+            hash.add_to_hash(read_usize(bytes));
+            bytes = &bytes[size_of::<usize>()..];
+        }
+
+        // The tail is shorter than a word, so it fits in a single one.
+        if !bytes.is_empty() {
+            let mut tail = 0;
+            let mut shift = 0;
+            if (size_of::<usize>() > 4) && (bytes.len() >= 4) {
+                tail |= read_u32(bytes) as usize;
+                shift += 32;
+                bytes = &bytes[4..];
+            }
+            if (size_of::<usize>() > 2) && bytes.len() >= 2 {
+                tail |= (read_u16(bytes) as usize) << shift;
+                shift += 16;
+                bytes = &bytes[2..];
+            }
+            if (size_of::<usize>() > 1) && !bytes.is_empty() {
+                tail |= (bytes[0] as usize) << shift;
+            }
+            hash.add_to_hash(tail);
+        }
+        self.inner.hash = hash.hash;
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.inner.write_u8(i);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.inner.write_u16(i);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.inner.write_u32(i);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.inner.write_u64(i);
+    }
+
+    #[inline]
+    fn write_u128(&mut self, i: u128) {
+        self.inner.write_u128(i);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.inner.write_usize(i);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.inner.finish()
+    }
+}
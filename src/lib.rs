@@ -10,16 +10,26 @@
 
 //! Fast, non-cryptographic hash used by rustc and Firefox.
 //!
+//! # Versions
+//!
+//! The hashes produced by `FxHasher` are not guaranteed to stay the same
+//! across releases of this crate: it is the latest version of the algorithm
+//! that was made the default, currently [`v1`]. Each version is also available
+//! in its own module, and newer versions such as [`v2`] are first made
+//! available there so that they can be evaluated. The hashes produced by
+//! those modules never change. Code that persists hashes, for instance in
+//! on-disk caches, should use a versioned module explicitly.
+//!
 //! # Example
 //!
 //! ```rust
-//! # #[cfg(feature = "std")]
+//! # #[cfg(any(feature = "std", feature = "hashbrown"))]
 //! # fn main() {
 //! use rustc_hash::FxHashMap;
 //! let mut map: FxHashMap<u32, u32> = FxHashMap::default();
 //! map.insert(22, 44);
 //! # }
-//! # #[cfg(not(feature = "std"))]
+//! # #[cfg(not(any(feature = "std", feature = "hashbrown")))]
 //! # fn main() { }
 //! ```
 
@@ -28,121 +38,232 @@
 #[cfg(feature = "std")]
 extern crate std;
 
-use core::convert::TryInto;
-use core::default::Default;
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+extern crate alloc;
+
+#[cfg(feature = "hashbrown")]
+extern crate hashbrown;
+
+#[cfg(feature = "derive")]
+extern crate rustc_hash_derive;
+
 #[cfg(feature = "std")]
-use core::hash::BuildHasherDefault;
-use core::hash::Hasher;
-use core::mem::size_of;
-use core::ops::BitXor;
+mod adaptive;
+#[cfg(feature = "collisions")]
+pub mod collisions;
+mod fixed_width;
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod frozen;
+mod fx_hash;
+mod global_seed;
+mod hash_stable;
+mod length_aware;
+mod mix;
+#[doc(hidden)]
+pub mod phf;
+mod random_state;
+mod set_digest;
+#[cfg(feature = "shuffle-iteration")]
+mod shuffle;
+mod stable;
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+mod unord;
+mod unordered;
+pub mod v1;
+pub mod v2;
+
+use core::hash::{BuildHasher, Hash};
+#[cfg(all(not(feature = "std"), feature = "hashbrown"))]
+use hashbrown::{HashMap, HashSet};
 #[cfg(feature = "std")]
 use std::collections::{HashMap, HashSet};
 
-/// Type alias for a hashmap using the `fx` hash algorithm.
 #[cfg(feature = "std")]
-pub type FxHashMap<K, V> = HashMap<K, V, BuildHasherDefault<FxHasher>>;
+pub use adaptive::FxAdaptiveMap;
+pub use fixed_width::{FxHasher32, FxHasher64};
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use frozen::FrozenFxMap;
+#[doc(hidden)]
+pub use fx_hash::__write_packed;
+pub use fx_hash::FxHash;
+pub use global_seed::{global_seed, set_global_seed, FxGlobalSeedState};
+pub use hash_stable::HashStable;
+pub use length_aware::FxLengthAwareHasher;
+pub use phf::{FxPhfMap, FxPhfSet};
+pub use random_state::{FxRandomHasher, FxRandomState};
+#[cfg(feature = "derive")]
+pub use rustc_hash_derive::{FxHash, HashStable};
+pub use set_digest::FxSetDigest;
+#[cfg(feature = "shuffle-iteration")]
+pub use shuffle::shuffle_seed;
+pub use stable::{Fingerprint, ParseFingerprintError, StableHasher};
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use unord::{FxUnordMap, FxUnordSet, UnordItems};
+pub use unordered::hash_unordered;
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub use unordered::{hash_unordered_map, hash_unordered_set, UnorderedMap, UnorderedSet};
 
-/// Type alias for a hashmap using the `fx` hash algorithm.
-#[cfg(feature = "std")]
-pub type FxHashSet<V> = HashSet<V, BuildHasherDefault<FxHasher>>;
-
-/// A speedy hash algorithm for use within rustc. The hashmap in liballoc
-/// by default uses SipHash which isn't quite as speedy as we want. In the
-/// compiler we're not really worried about DOS attempts, so we use a fast
-/// non-cryptographic hash.
-///
-/// This is the same as the algorithm used by Firefox -- which is a homespun
-/// one not based on any widely-known algorithm -- though modified to produce
-/// 64-bit hash values instead of 32-bit hash values. It consistently
-/// out-performs an FNV-based hash within rustc itself -- the collision rate is
-/// similar or slightly worse than FNV, but the speed of the hash function
-/// itself is much higher because it works on up to 8 bytes at a time.
-pub struct FxHasher {
-    hash: usize,
-}
+/// The latest default version of the `fx` algorithm, currently
+/// [`v1::FxHasher`].
+pub use v1::FxHasher;
 
-#[cfg(target_pointer_width = "32")]
-const K: usize = 0x9e3779b9;
-#[cfg(target_pointer_width = "64")]
-const K: usize = 0x517cc1b727220a95;
+/// A `BuildHasher` creating default `FxHasher`s, as used by `FxHashMap` and
+/// `FxHashSet`.
+///
+/// It can be created in `const` contexts. Since Rust 1.85, where
+/// `HashMap::with_hasher` is `const`, a `static` map can then be initialized
+/// with `HashMap::with_hasher(FxBuildHasher)`; on older versions, it can be
+/// created on first use as below.
+///
+/// With the `shuffle-iteration` feature, the hashers start from a seed chosen
+/// once per process, see `shuffle_seed`, so that the iteration order of maps
+/// and sets changes from one run to the next. This is meant to be enabled
+/// for tests only, for instance in `dev-dependencies`, to find code that
+/// depends on the iteration order. The hashes of `fx_hash_one` and of the
+/// `const` helpers are not seeded, and then differ from those of the maps.
+///
+/// # Example
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use std::sync::Mutex;
+/// use rustc_hash::FxBuildHasher;
+///
+/// static CACHE: Mutex<Option<HashMap<u32, u32, FxBuildHasher>>> = Mutex::new(None);
+///
+/// let mut cache = CACHE.lock().unwrap();
+/// let cache = cache.get_or_insert_with(|| HashMap::with_hasher(FxBuildHasher));
+/// cache.insert(22, 44);
+/// assert_eq!(cache.get(&22), Some(&44));
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FxBuildHasher;
 
-impl Default for FxHasher {
+impl FxBuildHasher {
+    /// Calculates the hash of a single value, exactly as `FxHashMap` and
+    /// `FxHashSet` do.
+    ///
+    /// This is `BuildHasher::hash_one`, available without importing the
+    /// trait.
     #[inline]
-    fn default() -> FxHasher {
-        FxHasher { hash: 0 }
+    pub fn hash_one<T: Hash>(&self, x: T) -> u64 {
+        BuildHasher::hash_one(self, x)
     }
 }
 
-impl FxHasher {
+impl BuildHasher for FxBuildHasher {
+    type Hasher = FxHasher;
+
     #[inline]
-    fn add_to_hash(&mut self, i: usize) {
-        self.hash = self.hash.rotate_left(5).bitxor(i).wrapping_mul(K);
+    fn build_hasher(&self) -> FxHasher {
+        #[cfg(feature = "shuffle-iteration")]
+        return FxHasher::with_seed(mix::mix_seed(shuffle_seed()));
+        #[cfg(not(feature = "shuffle-iteration"))]
+        FxHasher::default()
     }
 }
 
-impl Hasher for FxHasher {
-    #[inline]
-    fn write(&mut self, mut bytes: &[u8]) {
-        #[cfg(target_pointer_width = "32")]
-        let read_usize = |bytes: &[u8]| u32::from_ne_bytes(bytes[..4].try_into().unwrap());
-        #[cfg(target_pointer_width = "64")]
-        let read_usize = |bytes: &[u8]| u64::from_ne_bytes(bytes[..8].try_into().unwrap());
-
-        let mut hash = FxHasher { hash: self.hash };
-        assert!(size_of::<usize>() <= 8);
-        while bytes.len() >= size_of::<usize>() {
-            hash.add_to_hash(read_usize(bytes) as usize);
-            bytes = &bytes[size_of::<usize>()..];
-        }
-        if (size_of::<usize>() > 4) && (bytes.len() >= 4) {
-            hash.add_to_hash(u32::from_ne_bytes(bytes[..4].try_into().unwrap()) as usize);
-            bytes = &bytes[4..];
-        }
-        if (size_of::<usize>() > 2) && bytes.len() >= 2 {
-            hash.add_to_hash(u16::from_ne_bytes(bytes[..2].try_into().unwrap()) as usize);
-            bytes = &bytes[2..];
-        }
-        if (size_of::<usize>() > 1) && bytes.len() >= 1 {
-            hash.add_to_hash(bytes[0] as usize);
-        }
-        self.hash = hash.hash;
-    }
+/// Calculates the `FxHasher` hash of a single value.
+///
+/// This is the hash `FxHashMap` and `FxHashSet` use for the value, unless
+/// the `shuffle-iteration` feature is enabled.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(not(feature = "shuffle-iteration"))]
+/// # fn main() {
+/// use rustc_hash::{fx_hash_one, FxBuildHasher};
+///
+/// let key = (22u32, "x");
+/// assert_eq!(fx_hash_one(&key), FxBuildHasher.hash_one(&key));
+/// # }
+/// # #[cfg(feature = "shuffle-iteration")]
+/// # fn main() { }
+/// ```
+#[inline]
+pub fn fx_hash_one<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = FxHasher::default();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
 
-    #[inline]
-    fn write_u8(&mut self, i: u8) {
-        self.add_to_hash(i as usize);
-    }
+/// Calculates the `FxHasher` hash of a byte buffer, passed to `Hasher::write`
+/// as a whole.
+///
+/// Unlike `fx_hash_one(bytes)`, this does not hash the length of the buffer
+/// first, so it is the hash of the raw bytes. It can be computed in `const`
+/// contexts.
+#[inline]
+pub const fn fx_hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hasher = FxHasher::new();
+    hasher.write_bytes(bytes);
+    hasher.finish()
+}
 
-    #[inline]
-    fn write_u16(&mut self, i: u16) {
-        self.add_to_hash(i as usize);
-    }
+/// Calculates the `FxHasher` hash of a string.
+///
+/// The result currently matches the hash of a `String` or `&str` key in an
+/// `FxHashMap` or `FxHashSet`, unless the `shuffle-iteration` feature is
+/// enabled. This relies on `impl Hash for str` writing the bytes of the
+/// string followed by `0xff`, which is an implementation detail of `std` that
+/// a future release may change; the example below checks it. The hash can be
+/// computed in `const` contexts, for instance to dispatch on the hash of a
+/// string.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(all(feature = "std", not(feature = "shuffle-iteration")))]
+/// # fn main() {
+/// use rustc_hash::{fx_hash_str, FxHashMap};
+///
+/// const RUSTC: u64 = fx_hash_str("rustc");
+///
+/// let mut map: FxHashMap<String, u32> = FxHashMap::default();
+/// map.insert("rustc".to_string(), 1);
+/// assert_eq!(RUSTC, map.hasher().hash_one(&"rustc".to_string()));
+/// assert_eq!(RUSTC, fx_hash_str("rustc"));
+/// # }
+/// # #[cfg(not(all(feature = "std", not(feature = "shuffle-iteration"))))]
+/// # fn main() { }
+/// ```
+#[inline]
+pub const fn fx_hash_str(s: &str) -> u64 {
+    // This is what `impl Hash for str` does.
+    let mut hasher = FxHasher::new();
+    hasher.write_bytes(s.as_bytes());
+    hasher.write_u8(0xff);
+    hasher.finish()
+}
 
-    #[inline]
-    fn write_u32(&mut self, i: u32) {
-        self.add_to_hash(i as usize);
-    }
+/// Type alias for a hashmap using the `fx` hash algorithm.
+///
+/// Without the `std` feature, this is a `hashbrown::HashMap` when the
+/// `hashbrown` feature is enabled. The same goes for the other aliases below.
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub type FxHashMap<K, V> = HashMap<K, V, FxBuildHasher>;
 
-    #[cfg(target_pointer_width = "32")]
-    #[inline]
-    fn write_u64(&mut self, i: u64) {
-        self.add_to_hash(i as usize);
-        self.add_to_hash((i >> 32) as usize);
-    }
+/// Type alias for a hashmap using the `fx` hash algorithm.
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub type FxHashSet<V> = HashSet<V, FxBuildHasher>;
 
-    #[cfg(target_pointer_width = "64")]
-    #[inline]
-    fn write_u64(&mut self, i: u64) {
-        self.add_to_hash(i as usize);
-    }
+/// Type alias for a hashmap using the `fx` hash algorithm with a random seed
+/// chosen for each map.
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub type FxRandomHashMap<K, V> = HashMap<K, V, FxRandomState>;
 
-    #[inline]
-    fn write_usize(&mut self, i: usize) {
-        self.add_to_hash(i);
-    }
+/// Type alias for a hashset using the `fx` hash algorithm with a random seed
+/// chosen for each set.
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub type FxRandomHashSet<V> = HashSet<V, FxRandomState>;
 
-    #[inline]
-    fn finish(&self) -> u64 {
-        self.hash as u64
-    }
-}
+/// Type alias for a hashmap using the `fx` hash algorithm with the
+/// process-wide seed set by `set_global_seed`.
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub type FxGlobalSeedHashMap<K, V> = HashMap<K, V, FxGlobalSeedState>;
+
+/// Type alias for a hashset using the `fx` hash algorithm with the
+/// process-wide seed set by `set_global_seed`.
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub type FxGlobalSeedHashSet<V> = HashSet<V, FxGlobalSeedState>;
@@ -0,0 +1,23 @@
+/// Mixes a seed into the initial state of an `FxHasher`.
+///
+/// The initial state is only xored with the first word hashed, so small seeds
+/// used as is shift all the hashes by a multiple of the bucket count and keep
+/// the same iteration order. The finalizer of SplitMix64 is a bijection that
+/// spreads every bit of the seed, and maps zero to zero.
+#[inline]
+pub(crate) const fn mix_seed(seed: u64) -> u64 {
+    let mut z = seed;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Multiplies `x` and `y` into a 128-bit product and xors its two halves.
+///
+/// The low bits of a plain multiplication only depend on the low bits of its
+/// operands, while every bit of the high half depends on all of them.
+#[inline]
+pub(crate) const fn folded_multiply(x: u64, y: u64) -> u64 {
+    let full = (x as u128) * (y as u128);
+    (full as u64) ^ ((full >> 64) as u64)
+}
@@ -0,0 +1,603 @@
+//! Perfect hash tables built at compile time with `fx_phf!` and
+//! `fx_phf_set!`.
+//!
+//! The keys of a table are hashed with a seeded hash, and a seed and a pair of
+//! displacements per bucket are searched for during const evaluation so that
+//! every key ends up in its own slot (the "hash, displace and compress"
+//! scheme). A lookup is then one hash, one slot computation and a single key
+//! comparison, and building the table costs nothing at runtime.
+
+use core::fmt;
+use core::hash::Hasher;
+use core::ops::Index;
+
+use crate::mix::{folded_multiply, mix_seed};
+
+// The number of seeds tried before giving up on building a table. With
+// distinct keys, a seed only fails with a probability of about `1 / N`.
+const MAX_SEEDS: u64 = 16;
+
+// The multipliers of `SeededHasher`, as in `v2`.
+const K: u64 = 0xf1357aea2e62a9c5;
+const K_FINISH: u64 = 0x9e3779b97f4a7c15;
+
+// The leading digits of the fractional part of pi, so that the state does not
+// start at zero for the seed zero.
+const INITIAL_STATE: u64 = 0x243f6a8885a308d3;
+
+// Marks a slot that no key was displaced to yet.
+const EMPTY: u32 = u32::MAX;
+
+/// The hasher of the keys of perfect hash tables, also used by
+/// `FrozenFxMap`.
+///
+/// A seeded `FxHasher` only starts from its seed, and the differences between
+/// similar keys cancel out in the same way for many seeds, so that no seed
+/// separates them. Here each word is absorbed by a folded multiplication of
+/// the state with the word xored in, whose result depends on every bit of
+/// both, and the mixed seed is xored into the state at the start and before
+/// the final multiplication, so that each seed gives unrelated hashes.
+#[derive(Clone, Debug)]
+pub(crate) struct SeededHasher {
+    hash: u64,
+    key: u64,
+}
+
+// Loads up to 8 bytes as a little-endian integer.
+#[inline]
+const fn load_le(bytes: &[u8]) -> u64 {
+    let mut word = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        word |= (bytes[i] as u64) << (8 * i);
+        i += 1;
+    }
+    word
+}
+
+impl SeededHasher {
+    #[inline]
+    pub(crate) const fn new(seed: u64) -> SeededHasher {
+        let key = mix_seed(seed);
+        SeededHasher {
+            hash: key ^ INITIAL_STATE,
+            key,
+        }
+    }
+
+    #[inline]
+    pub(crate) const fn write_u64(&mut self, i: u64) {
+        self.hash = folded_multiply(self.hash ^ i, K);
+    }
+
+    #[inline]
+    pub(crate) const fn write_bytes(&mut self, bytes: &[u8]) {
+        let mut bytes = bytes;
+        while bytes.len() >= 8 {
+            let (word, rest) = bytes.split_at(8);
+            self.write_u64(load_le(word));
+            bytes = rest;
+        }
+        // The tail is shorter than a word, so it fits below the top byte,
+        // which records its length.
+        self.write_u64(load_le(bytes) | (bytes.len() as u64) << 56);
+    }
+
+    #[inline]
+    pub(crate) const fn finish(&self) -> u64 {
+        folded_multiply(self.hash ^ self.key, K_FINISH)
+    }
+}
+
+impl Hasher for SeededHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        self.write_bytes(bytes);
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        SeededHasher::write_u64(self, i);
+    }
+
+    #[inline]
+    fn write_u128(&mut self, i: u128) {
+        self.write_u64(i as u64);
+        self.write_u64((i >> 64) as u64);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        SeededHasher::finish(self)
+    }
+}
+
+/// Splits the hash of a key into a bucket and the two values displaced by
+/// it.
+#[inline]
+pub(crate) const fn split(hash: u64) -> (u32, u32, u32) {
+    // The low bits of an `fx` hash are poorly mixed, so mix the hash before
+    // using it modulo the size of the table.
+    let a = folded_multiply(hash, 0x9e3779b97f4a7c15);
+    let b = folded_multiply(a, 0x517cc1b727220a95);
+    ((a >> 32) as u32, a as u32, b as u32)
+}
+
+/// Hashes `key` like `impl Hash for str`, starting from `seed`, and splits the
+/// hash.
+#[inline]
+const fn hashes(seed: u64, key: &str) -> (u32, u32, u32) {
+    let mut hasher = SeededHasher::new(seed);
+    hasher.write_bytes(key.as_bytes());
+    hasher.write_u64(0xff);
+    split(hasher.finish())
+}
+
+/// Returns the slot of a key with hashes `f1` and `f2`, displaced by
+/// `(d1, d2)`, in a table of `len` slots.
+#[inline]
+pub(crate) const fn displace(f1: u32, f2: u32, (d1, d2): (u32, u32), len: usize) -> usize {
+    let slot = (f1 as u64) + (d1 as u64) * (f2 as u64) + (d2 as u64);
+    (slot % len as u64) as usize
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// The perfect hash of a set of keys, computed by `fx_phf!` and `fx_phf_set!`.
+#[doc(hidden)]
+pub struct Table<const N: usize> {
+    pub seed: u64,
+    pub displacements: [(u32, u32); N],
+    /// The index of the entry stored in each slot.
+    pub slots: [u32; N],
+}
+
+impl<const N: usize> Table<N> {
+    /// Builds the table for `keys`, which must hold `N` distinct keys.
+    pub const fn build(keys: &[&str]) -> Table<N> {
+        assert!(keys.len() == N);
+        assert!(N < EMPTY as usize, "too many keys");
+        let mut seed = 0;
+        while seed < MAX_SEEDS {
+            if let Some(table) = Table::try_build(keys, seed) {
+                return table;
+            }
+            seed += 1;
+        }
+        panic!("could not find a perfect hash for these keys with any of 16 seeds");
+    }
+
+    /// Tries to build the table with the given seed. Panics if two keys are
+    /// equal, which is only checked for keys whose hashes collide.
+    const fn try_build(keys: &[&str], seed: u64) -> Option<Table<N>> {
+        let mut table = Table {
+            seed,
+            displacements: [(0, 0); N],
+            slots: [EMPTY; N],
+        };
+
+        // Group the keys by bucket: the keys of bucket `b` are
+        // `members[starts[b]..starts[b] + sizes[b]]`.
+        let mut hashes = [(0, 0, 0); N];
+        let mut sizes = [0usize; N];
+        let mut i = 0;
+        while i < N {
+            hashes[i] = self::hashes(seed, keys[i]);
+            hashes[i].0 %= N as u32;
+            sizes[hashes[i].0 as usize] += 1;
+            i += 1;
+        }
+        let mut starts = [0usize; N];
+        let mut max_size = 0;
+        let mut b = 0;
+        while b < N {
+            if b > 0 {
+                starts[b] = starts[b - 1] + sizes[b - 1];
+            }
+            if sizes[b] > max_size {
+                max_size = sizes[b];
+            }
+            b += 1;
+        }
+        let mut members = [0usize; N];
+        let mut filled = [0usize; N];
+        i = 0;
+        while i < N {
+            let b = hashes[i].0 as usize;
+            members[starts[b] + filled[b]] = i;
+            filled[b] += 1;
+            i += 1;
+        }
+
+        // Keys with the same bucket and displaced values can never be
+        // separated, either because they are equal or because this seed is
+        // unlucky.
+        let mut m = 0;
+        while m < N {
+            let mut other = m + 1;
+            let b = hashes[members[m]].0 as usize;
+            while other < starts[b] + sizes[b] {
+                let (x, y) = (hashes[members[m]], hashes[members[other]]);
+                if x.1 == y.1 && x.2 == y.2 {
+                    if str_eq(keys[members[m]], keys[members[other]]) {
+                        panic!("duplicate key in perfect hash table");
+                    }
+                    return None;
+                }
+                other += 1;
+            }
+            m += 1;
+        }
+
+        // Place the largest buckets first, while most slots are still free.
+        let mut size = max_size;
+        while size > 1 {
+            let mut b = 0;
+            while b < N {
+                if sizes[b] == size {
+                    let bucket = (starts[b], size);
+                    match Table::find_displacement(&table.slots, &hashes, &members, bucket) {
+                        Some(d) => {
+                            table.displacements[b] = d;
+                            let mut m = starts[b];
+                            while m < starts[b] + size {
+                                let (_, f1, f2) = hashes[members[m]];
+                                table.slots[displace(f1, f2, d, N)] = members[m] as u32;
+                                m += 1;
+                            }
+                        }
+                        None => return None,
+                    }
+                }
+                b += 1;
+            }
+            size -= 1;
+        }
+
+        // A bucket holding a single key can send it to any free slot with
+        // `d1 = 0`, so fill the remaining slots in order.
+        let mut free = 0;
+        b = 0;
+        while b < N {
+            if sizes[b] == 1 {
+                while table.slots[free] != EMPTY {
+                    free += 1;
+                }
+                let key = members[starts[b]];
+                let f1 = hashes[key].1 as usize % N;
+                let d = (0, ((free + N - f1) % N) as u32);
+                table.displacements[b] = d;
+                table.slots[free] = key as u32;
+            }
+            b += 1;
+        }
+        Some(table)
+    }
+
+    /// Finds a displacement sending all the keys of a bucket to distinct free
+    /// slots.
+    const fn find_displacement(
+        slots: &[u32; N],
+        hashes: &[(u32, u32, u32); N],
+        members: &[usize; N],
+        (start, size): (usize, usize),
+    ) -> Option<(u32, u32)> {
+        let mut d1 = 0;
+        while d1 < N {
+            let mut d2 = 0;
+            while d2 < N {
+                let d = (d1 as u32, d2 as u32);
+                let mut ok = true;
+                let mut m = start;
+                while ok && m < start + size {
+                    let (_, f1, f2) = hashes[members[m]];
+                    let slot = displace(f1, f2, d, N);
+                    ok = slots[slot] == EMPTY;
+                    let mut other = start;
+                    while ok && other < m {
+                        let (_, g1, g2) = hashes[members[other]];
+                        ok = displace(g1, g2, d, N) != slot;
+                        other += 1;
+                    }
+                    m += 1;
+                }
+                if ok {
+                    return Some(d);
+                }
+                d2 += 1;
+            }
+            d1 += 1;
+        }
+        None
+    }
+}
+
+/// An immutable map from strings to values, built at compile time with
+/// [`fx_phf!`](crate::fx_phf).
+///
+/// Lookups cost one hash and at most one key comparison. Iteration
+/// follows the order in which the entries were written.
+pub struct FxPhfMap<V: 'static> {
+    seed: u64,
+    displacements: &'static [(u32, u32)],
+    slots: &'static [u32],
+    entries: &'static [(&'static str, V)],
+}
+
+impl<V> FxPhfMap<V> {
+    #[doc(hidden)]
+    pub const fn __new(
+        seed: u64,
+        displacements: &'static [(u32, u32)],
+        slots: &'static [u32],
+        entries: &'static [(&'static str, V)],
+    ) -> FxPhfMap<V> {
+        FxPhfMap {
+            seed,
+            displacements,
+            slots,
+            entries,
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the key and the value corresponding to `key`.
+    #[inline]
+    pub fn get_key_value(&self, key: &str) -> Option<(&'static str, &V)> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let len = self.entries.len();
+        let (bucket, f1, f2) = hashes(self.seed, key);
+        let d = self.displacements[bucket as usize % len];
+        let (k, v) = &self.entries[self.slots[displace(f1, f2, d, len)] as usize];
+        if *k == key {
+            Some((k, v))
+        } else {
+            None
+        }
+    }
+
+    /// Returns a reference to the value corresponding to `key`.
+    #[inline]
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.get_key_value(key).map(|(_, v)| v)
+    }
+
+    /// Returns `true` if the map contains a value for `key`.
+    #[inline]
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get_key_value(key).is_some()
+    }
+
+    /// An iterator visiting all key-value pairs, in the order they were written.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &V)> + '_ {
+        self.entries.iter().map(|(k, v)| (*k, v))
+    }
+
+    /// An iterator visiting all keys, in the order they were written.
+    pub fn keys(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.entries.iter().map(|(k, _)| *k)
+    }
+
+    /// An iterator visiting all values, in the order they were written.
+    pub fn values(&self) -> impl Iterator<Item = &V> + '_ {
+        self.entries.iter().map(|(_, v)| v)
+    }
+}
+
+impl<V> Index<&str> for FxPhfMap<V> {
+    type Output = V;
+
+    /// Returns a reference to the value corresponding to `key`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `key` is not present in the map.
+    #[inline]
+    fn index(&self, key: &str) -> &V {
+        self.get(key).expect("key not found in FxPhfMap")
+    }
+}
+
+impl<V: fmt::Debug> fmt::Debug for FxPhfMap<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// An immutable set of strings, built at compile time with
+/// [`fx_phf_set!`](crate::fx_phf_set).
+///
+/// Lookups cost one hash and at most one key comparison. Iteration
+/// follows the order in which the keys were written.
+pub struct FxPhfSet {
+    map: FxPhfMap<()>,
+}
+
+impl FxPhfSet {
+    #[doc(hidden)]
+    pub const fn __new(map: FxPhfMap<()>) -> FxPhfSet {
+        FxPhfSet { map }
+    }
+
+    /// Returns the number of keys in the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the set contains no keys.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns `true` if the set contains `key`.
+    #[inline]
+    pub fn contains(&self, key: &str) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Returns the key in the set equal to `key`.
+    #[inline]
+    pub fn get(&self, key: &str) -> Option<&'static str> {
+        self.map.get_key_value(key).map(|(k, _)| k)
+    }
+
+    /// An iterator visiting all keys, in the order they were written.
+    pub fn iter(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.map.keys()
+    }
+}
+
+impl fmt::Debug for FxPhfSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// Defines a `static` [`FxPhfMap`] from string keys to values, whose perfect
+/// hash is computed at compile time.
+///
+/// Keys must be string literals or constants, and values must be constant
+/// expressions. Duplicate keys are a compile-time error.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate rustc_hash;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Keyword {
+///     Fn,
+///     Let,
+///     Match,
+/// }
+///
+/// fx_phf! {
+///     static KEYWORDS: FxPhfMap<Keyword> = {
+///         "fn" => Keyword::Fn,
+///         "let" => Keyword::Let,
+///         "match" => Keyword::Match,
+///     };
+/// }
+///
+/// fn main() {
+///     assert_eq!(KEYWORDS.get("let"), Some(&Keyword::Let));
+///     assert_eq!(KEYWORDS["match"], Keyword::Match);
+///     assert!(!KEYWORDS.contains_key("struct"));
+///     assert_eq!(KEYWORDS.keys().collect::<Vec<_>>(), ["fn", "let", "match"]);
+/// }
+/// ```
+#[macro_export]
+macro_rules! fx_phf {
+    ($(
+        $(#[$attr:meta])*
+        $vis:vis static $name:ident : FxPhfMap<$value:ty> = {
+            $($key:expr => $val:expr),* $(,)?
+        };
+    )*) => {$(
+        $(#[$attr])*
+        $vis static $name: $crate::FxPhfMap<$value> = {
+            const KEYS: &[&str] = &[$($key),*];
+            const LEN: usize = KEYS.len();
+            const TABLE: $crate::phf::Table<LEN> = $crate::phf::Table::build(KEYS);
+            const DISPLACEMENTS: [(u32, u32); LEN] = TABLE.displacements;
+            const SLOTS: [u32; LEN] = TABLE.slots;
+            const ENTRIES: &[(&str, $value)] = &[$(($key, $val)),*];
+            $crate::FxPhfMap::__new(TABLE.seed, &DISPLACEMENTS, &SLOTS, ENTRIES)
+        };
+    )*};
+}
+
+/// Defines a `static` [`FxPhfSet`] of strings, whose perfect hash is computed
+/// at compile time.
+///
+/// Keys must be string literals or constants. Duplicate keys are a
+/// compile-time error.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate rustc_hash;
+///
+/// fx_phf_set! {
+///     static INTRINSICS: FxPhfSet = { "abort", "size_of", "transmute" };
+/// }
+///
+/// fn main() {
+///     assert!(INTRINSICS.contains("size_of"));
+///     assert!(!INTRINSICS.contains("align_of"));
+///     assert_eq!(INTRINSICS.len(), 3);
+/// }
+/// ```
+#[macro_export]
+macro_rules! fx_phf_set {
+    ($(
+        $(#[$attr:meta])*
+        $vis:vis static $name:ident : FxPhfSet = { $($key:expr),* $(,)? };
+    )*) => {$(
+        $(#[$attr])*
+        $vis static $name: $crate::FxPhfSet = {
+            const KEYS: &[&str] = &[$($key),*];
+            const LEN: usize = KEYS.len();
+            const TABLE: $crate::phf::Table<LEN> = $crate::phf::Table::build(KEYS);
+            const DISPLACEMENTS: [(u32, u32); LEN] = TABLE.displacements;
+            const SLOTS: [u32; LEN] = TABLE.slots;
+            const ENTRIES: &[(&str, ())] = &[$(($key, ())),*];
+            $crate::FxPhfSet::__new($crate::FxPhfMap::__new(
+                TABLE.seed,
+                &DISPLACEMENTS,
+                &SLOTS,
+                ENTRIES,
+            ))
+        };
+    )*};
+}
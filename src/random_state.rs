@@ -0,0 +1,175 @@
+use core::hash::{BuildHasher, Hasher};
+#[cfg(feature = "std")]
+use std::collections::hash_map::RandomState;
+
+use crate::mix::{folded_multiply, mix_seed};
+use crate::v1::FxHasher;
+
+// The multiplier of the final mix, the digits of pi.
+const MIX_MUL: u64 = 0x243f6a8885a308d3;
+
+/// A `BuildHasher` creating `FxRandomHasher`s keyed by a seed.
+///
+/// With the `std` feature, `FxRandomState::new()` draws a fresh random seed
+/// for every state, so two maps built from different states iterate over the
+/// same keys in a different order. Without `std`, a seed has to be supplied
+/// with `FxRandomState::with_seed`.
+///
+/// The seed is mixed, so that consecutive seeds give unrelated orders, and is
+/// used both as the initial state of an `FxHasher` and as the key of a final
+/// folded multiply. The low bits of an `FxHasher` hash only depend on the low
+/// bits of the words added to it, so keys that only differ in their high bits
+/// would otherwise all land in the same bucket, whatever the seed. With the
+/// final mix, every bit of the hash depends on the seed and on every bit of
+/// the state, and such keys spread over the buckets.
+///
+/// This does not make `FxHasher` a keyed hash: some inputs reach the same
+/// `FxHasher` state whatever its initial state, for instance two words where
+/// the first ones only differ in their top bit, and the second ones in the
+/// bit of value 16, and no final mix can separate them. Use `FxAdaptiveMap`,
+/// or the `RandomState` of `std`, for keys that come from an untrusted
+/// source.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "std")]
+/// # fn main() {
+/// use rustc_hash::{FxRandomHashMap, FxRandomState};
+///
+/// let mut map: FxRandomHashMap<u32, u32> = FxRandomHashMap::default();
+/// map.insert(22, 44);
+///
+/// let mut seeded = FxRandomHashMap::with_hasher(FxRandomState::with_seed(7));
+/// seeded.insert(22, 44);
+/// assert_eq!(map, seeded);
+/// # }
+/// # #[cfg(not(feature = "std"))]
+/// # fn main() { }
+/// ```
+///
+/// Keys that only differ in their high bits do not collide in their low
+/// bits, and which of them share a bucket depends on the seed:
+///
+/// ```rust
+/// # #[cfg(feature = "std")]
+/// # fn main() {
+/// use std::collections::HashSet;
+/// use std::hash::BuildHasher;
+/// use rustc_hash::FxRandomState;
+///
+/// let shift = usize::BITS / 2;
+/// let buckets = |state: &FxRandomState| -> Vec<u16> {
+///     (0..1000usize).map(|i| state.hash_one(i << shift) as u16).collect()
+/// };
+/// for seed in 0..10 {
+///     let state = FxRandomState::with_seed(seed);
+///     let low = buckets(&state);
+///     // Random values would give about 8 collisions.
+///     assert!(low.iter().collect::<HashSet<_>>().len() > 980);
+///     assert_ne!(low, buckets(&FxRandomState::with_seed(seed + 1)));
+/// }
+/// # }
+/// # #[cfg(not(feature = "std"))]
+/// # fn main() { }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct FxRandomState {
+    seed: u64,
+    key: u64,
+}
+
+impl FxRandomState {
+    /// Creates a new `FxRandomState` with a randomly chosen seed.
+    #[cfg(feature = "std")]
+    pub fn new() -> FxRandomState {
+        // `RandomState` is seeded from the OS once per thread and then
+        // perturbed for every instance, which is exactly what we want.
+        let seed = RandomState::new().build_hasher().finish();
+        FxRandomState::with_seed(seed)
+    }
+
+    /// Creates a new `FxRandomState` using the given seed.
+    pub fn with_seed(seed: u64) -> FxRandomState {
+        FxRandomState {
+            seed,
+            key: mix_seed(seed),
+        }
+    }
+
+    /// Returns the seed used by the hashers built from this state.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for FxRandomState {
+    fn default() -> FxRandomState {
+        FxRandomState::new()
+    }
+}
+
+impl BuildHasher for FxRandomState {
+    type Hasher = FxRandomHasher;
+
+    #[inline]
+    fn build_hasher(&self) -> FxRandomHasher {
+        FxRandomHasher {
+            inner: FxHasher::with_seed(self.key),
+            key: self.key,
+        }
+    }
+}
+
+/// The hasher built by `FxRandomState`.
+///
+/// It writes values like the `FxHasher` it wraps, and finishes with a folded
+/// multiply of its state keyed by the seed of the `FxRandomState`.
+#[derive(Clone, Debug)]
+pub struct FxRandomHasher {
+    inner: FxHasher,
+    key: u64,
+}
+
+impl Hasher for FxRandomHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        self.inner.write(bytes);
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.inner.write_u8(i);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.inner.write_u16(i);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.inner.write_u32(i);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.inner.write_u64(i);
+    }
+
+    #[inline]
+    fn write_u128(&mut self, i: u128) {
+        self.inner.write_u128(i);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.inner.write_usize(i);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        folded_multiply(self.inner.finish() ^ self.key, MIX_MUL)
+    }
+}
@@ -0,0 +1,236 @@
+use core::hash::{Hash, Hasher};
+use core::iter::FromIterator;
+
+use crate::mix::folded_multiply;
+use crate::FxHasher64;
+
+// Constants of the two mixes of each element hash, the digits of pi and e.
+const MIX_LOW: (u64, u64) = (0x243f6a8885a308d3, 0xb7e151628aed2a6b);
+const MIX_HIGH: (u64, u64) = (0x13198a2e03707344, 0xbf7158809cf4f3c7);
+
+/// A digest of a multiset that is updated in constant time when an element is
+/// inserted or removed.
+///
+/// Each element is hashed with `FxHasher64`, and the hash is mixed with two
+/// folded multiplies into a 128-bit value. The digest is the wrapping sum of
+/// these values together with the number of elements, so inserting and
+/// removing elements commute, and removing an element undoes inserting it.
+/// Two multisets with the same elements therefore always have the same
+/// digest, whatever the order of the operations that built them, and digests
+/// of different multisets are equal with a probability of about 2^-64 for
+/// each pair of distinct element hashes.
+///
+/// Unlike the default `FxHasher`, `FxHasher64` gives the same hashes on every
+/// target and does not change between releases of this crate, so the bytes
+/// returned by `to_le_bytes` can be compared with the digest of a replica
+/// running on another machine. Both replicas must hash their elements the
+/// same way, though: the `Hash` implementations of `std` for slices and
+/// arrays of integers write them in the byte order of the target.
+///
+/// This is meant for comparing the contents of replicas cheaply, not as a
+/// protection against someone choosing the elements: the per-element hash is
+/// `FxHasher64`, which is easy to collide on purpose.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "std")]
+/// # fn main() {
+/// use rustc_hash::{FxHashSet, FxSetDigest};
+///
+/// // Two replicas receiving the same updates in a different order.
+/// let mut state = FxHashSet::default();
+/// let mut digest = FxSetDigest::new();
+/// for word in ["a", "b", "c"] {
+///     state.insert(word);
+///     digest.insert(word);
+/// }
+/// state.remove("b");
+/// digest.remove("b");
+///
+/// let mut other = FxSetDigest::new();
+/// other.insert("c");
+/// other.insert("a");
+/// assert_eq!(digest, other);
+/// assert_eq!(digest, state.iter().collect::<FxSetDigest>());
+///
+/// // The bytes sent to a replica are the same on every target.
+/// let bytes = digest.to_le_bytes();
+/// assert_eq!(bytes[..8], [0xab, 0xf2, 0x98, 0x22, 0x9d, 0x97, 0x04, 0x1a]);
+/// assert_eq!(FxSetDigest::from_le_bytes(bytes), digest);
+///
+/// // What changed between two replicas can be tracked the same way.
+/// let mut changes = FxSetDigest::new();
+/// changes.insert("d");
+/// other.merge(&changes);
+/// assert_ne!(digest, other);
+/// other.subtract(&changes);
+/// assert_eq!(digest, other);
+/// # }
+/// # #[cfg(not(feature = "std"))]
+/// # fn main() { }
+/// ```
+///
+/// Sets with equal contents have equal digests, however they were built:
+///
+/// ```rust
+/// # #[cfg(feature = "std")]
+/// # fn main() {
+/// use rustc_hash::{FxHashSet, FxSetDigest};
+///
+/// let mut rng = 0x2545f4914f6cdd1du64;
+/// let mut next = move || {
+///     rng ^= rng << 13;
+///     rng ^= rng >> 7;
+///     rng ^= rng << 17;
+///     rng
+/// };
+///
+/// for _ in 0..100 {
+///     // Apply random inserts and removes to a set and its digest.
+///     let mut set = FxHashSet::default();
+///     let mut digest = FxSetDigest::new();
+///     for _ in 0..200 {
+///         let value = next() % 64;
+///         if next() % 3 == 0 {
+///             if set.remove(&value) {
+///                 digest.remove(&value);
+///             }
+///         } else if set.insert(value) {
+///             digest.insert(&value);
+///         }
+///     }
+///
+///     // Rebuilding the set in another order gives the same digest.
+///     let mut values: Vec<u64> = set.iter().cloned().collect();
+///     values.sort_unstable();
+///     assert_eq!(digest, values.iter().rev().collect::<FxSetDigest>());
+///     assert_eq!(digest.len(), set.len() as u64);
+///
+///     // Any other set has a different digest.
+///     let missing = (0..64).find(|value| !set.contains(value));
+///     if let Some(value) = missing {
+///         let mut other = digest;
+///         other.insert(&value);
+///         assert_ne!(digest, other);
+///         other.remove(&value);
+///         assert_eq!(digest, other);
+///     }
+///     if let Some(&value) = values.first() {
+///         let mut other = digest;
+///         other.remove(&value);
+///         assert_ne!(digest, other);
+///     }
+/// }
+/// # }
+/// # #[cfg(not(feature = "std"))]
+/// # fn main() { }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FxSetDigest {
+    sum: u128,
+    len: u64,
+}
+
+#[inline]
+fn element_hash<T: Hash + ?Sized>(value: &T) -> u128 {
+    let mut hasher = FxHasher64::default();
+    value.hash(&mut hasher);
+    let hash = hasher.finish();
+    let low = folded_multiply(hash ^ MIX_LOW.0, MIX_LOW.1);
+    let high = folded_multiply(hash ^ MIX_HIGH.0, MIX_HIGH.1);
+    (high as u128) << 64 | low as u128
+}
+
+impl FxSetDigest {
+    /// Creates the digest of the empty multiset.
+    #[inline]
+    pub const fn new() -> FxSetDigest {
+        FxSetDigest { sum: 0, len: 0 }
+    }
+
+    /// Adds an element to the digest.
+    #[inline]
+    pub fn insert<T: Hash + ?Sized>(&mut self, value: &T) {
+        self.sum = self.sum.wrapping_add(element_hash(value));
+        self.len = self.len.wrapping_add(1);
+    }
+
+    /// Removes an element from the digest.
+    ///
+    /// This undoes a previous `insert` of an equal element. Removing an
+    /// element that was not inserted is allowed, and is undone by inserting
+    /// it.
+    #[inline]
+    pub fn remove<T: Hash + ?Sized>(&mut self, value: &T) {
+        self.sum = self.sum.wrapping_sub(element_hash(value));
+        self.len = self.len.wrapping_sub(1);
+    }
+
+    /// Adds all the elements of another digest to this one, as if they had
+    /// been inserted.
+    #[inline]
+    pub fn merge(&mut self, other: &FxSetDigest) {
+        self.sum = self.sum.wrapping_add(other.sum);
+        self.len = self.len.wrapping_add(other.len);
+    }
+
+    /// Removes all the elements of another digest from this one, as if they
+    /// had been removed.
+    #[inline]
+    pub fn subtract(&mut self, other: &FxSetDigest) {
+        self.sum = self.sum.wrapping_sub(other.sum);
+        self.len = self.len.wrapping_sub(other.len);
+    }
+
+    /// Returns the number of elements inserted minus the number of elements
+    /// removed, wrapping around.
+    #[inline]
+    pub const fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if this is the digest of the empty multiset.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.sum == 0 && self.len == 0
+    }
+
+    /// Returns the digest as 24 little-endian bytes, to be sent to a replica.
+    #[inline]
+    pub fn to_le_bytes(&self) -> [u8; 24] {
+        let mut bytes = [0; 24];
+        bytes[..16].copy_from_slice(&self.sum.to_le_bytes());
+        bytes[16..].copy_from_slice(&self.len.to_le_bytes());
+        bytes
+    }
+
+    /// Creates a digest from the bytes returned by `to_le_bytes`.
+    #[inline]
+    pub fn from_le_bytes(bytes: [u8; 24]) -> FxSetDigest {
+        let mut sum = [0; 16];
+        let mut len = [0; 8];
+        sum.copy_from_slice(&bytes[..16]);
+        len.copy_from_slice(&bytes[16..]);
+        FxSetDigest {
+            sum: u128::from_le_bytes(sum),
+            len: u64::from_le_bytes(len),
+        }
+    }
+}
+
+impl<T: Hash> Extend<T> for FxSetDigest {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(&value);
+        }
+    }
+}
+
+impl<T: Hash> FromIterator<T> for FxSetDigest {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> FxSetDigest {
+        let mut digest = FxSetDigest::new();
+        digest.extend(iter);
+        digest
+    }
+}
@@ -0,0 +1,84 @@
+use core::hash::{BuildHasher, Hasher};
+use std::collections::hash_map::RandomState;
+use std::env;
+use std::sync::OnceLock;
+
+/// The environment variable from which the seed is read.
+const SEED_VAR: &str = "RUSTC_HASH_SEED";
+
+static SEED: OnceLock<u64> = OnceLock::new();
+
+fn parse(value: &str) -> Option<u64> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Returns the seed of the `FxHasher`s built by `FxBuildHasher` with the
+/// `shuffle-iteration` feature.
+///
+/// The seed is read from the `RUSTC_HASH_SEED` environment variable, in
+/// decimal or in hexadecimal with a `0x` prefix, and is chosen randomly if
+/// the variable is not set. It is chosen once per process, the first time a
+/// hasher is built, and printed to standard error so that a failure can be
+/// replayed by setting `RUSTC_HASH_SEED` to the same value. The seed is mixed
+/// before it is given to the hashers, so that close values such as 1 and 2
+/// give unrelated iteration orders.
+///
+/// # Panics
+///
+/// Panics if `RUSTC_HASH_SEED` is set to something else than an integer.
+pub fn shuffle_seed() -> u64 {
+    *SEED.get_or_init(|| {
+        let seed = match env::var(SEED_VAR) {
+            Ok(value) => parse(&value)
+                .unwrap_or_else(|| panic!("{} must be an integer, not {:?}", SEED_VAR, value)),
+            Err(_) => RandomState::new().build_hasher().finish(),
+        };
+        std::eprintln!(
+            "rustc-hash: shuffling iteration order, replay with {}={}",
+            SEED_VAR,
+            seed
+        );
+        seed
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    #[test]
+    fn parse_decimal() {
+        assert_eq!(parse("0"), Some(0));
+        assert_eq!(parse("42"), Some(42));
+        assert_eq!(parse("18446744073709551615"), Some(u64::MAX));
+    }
+
+    #[test]
+    fn parse_hex() {
+        assert_eq!(parse("0x0"), Some(0));
+        assert_eq!(parse("0x2a"), Some(42));
+        assert_eq!(parse("0xDEADbeef"), Some(0xdeadbeef));
+        assert_eq!(parse("0xffffffffffffffff"), Some(u64::MAX));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for value in [
+            "",
+            "x",
+            "0x",
+            "-1",
+            "+0x1",
+            "1.0",
+            "0xg",
+            "2a",
+            " 1",
+            "18446744073709551616",
+        ] {
+            assert_eq!(parse(value), None, "{:?}", value);
+        }
+    }
+}
@@ -0,0 +1,352 @@
+use core::fmt;
+use core::hash::Hasher;
+use core::str::FromStr;
+
+/// A 128-bit hash of a value, as produced by `StableHasher`.
+///
+/// Fingerprints are meant to be persisted, for instance in incremental
+/// compilation caches, and compared across machines. They are displayed and
+/// parsed as 32 lowercase hexadecimal digits.
+///
+/// # Example
+///
+/// ```rust
+/// use rustc_hash::Fingerprint;
+///
+/// let a = Fingerprint::new(0x0123456789abcdef, 0xfedcba9876543210);
+/// let text = a.to_string();
+/// assert_eq!(text, "0123456789abcdeffedcba9876543210");
+/// assert_eq!(text.parse::<Fingerprint>(), Ok(a));
+///
+/// let b = Fingerprint::new(1, 2);
+/// assert_eq!(a.combine(b), Fingerprint::new(0x0369d0369d0369ce, 0xfc962fc962fc9632));
+/// assert_ne!(a.combine(b), b.combine(a));
+///
+/// assert!("0123".parse::<Fingerprint>().is_err());
+/// assert!("+123456789abcdeffedcba9876543210".parse::<Fingerprint>().is_err());
+/// assert_eq!(Fingerprint::from_le_bytes(a.to_le_bytes()), a);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fingerprint(pub u64, pub u64);
+
+impl Fingerprint {
+    /// The fingerprint whose two halves are zero.
+    pub const ZERO: Fingerprint = Fingerprint(0, 0);
+
+    /// Creates a fingerprint from its two halves.
+    #[inline]
+    pub const fn new(first: u64, second: u64) -> Fingerprint {
+        Fingerprint(first, second)
+    }
+
+    /// Returns the two halves of the fingerprint.
+    #[inline]
+    pub const fn as_value(self) -> (u64, u64) {
+        (self.0, self.1)
+    }
+
+    /// Combines two fingerprints into one, in an order-dependent way.
+    ///
+    /// This is the combination used by rustc: each half is multiplied by
+    /// three before the corresponding half of `other` is added.
+    #[inline]
+    pub const fn combine(self, other: Fingerprint) -> Fingerprint {
+        Fingerprint(
+            self.0.wrapping_mul(3).wrapping_add(other.0),
+            self.1.wrapping_mul(3).wrapping_add(other.1),
+        )
+    }
+
+    /// Combines two fingerprints into one, in a way that does not depend on
+    /// their order.
+    ///
+    /// The fingerprints are added as 128-bit integers, so combining the
+    /// fingerprints of the elements of a collection gives the same result
+    /// whatever order they are visited in.
+    #[inline]
+    pub const fn combine_commutative(self, other: Fingerprint) -> Fingerprint {
+        let a = (self.1 as u128) << 64 | self.0 as u128;
+        let b = (other.1 as u128) << 64 | other.0 as u128;
+        let c = a.wrapping_add(b);
+        Fingerprint(c as u64, (c >> 64) as u64)
+    }
+
+    /// Returns the fingerprint as 16 little-endian bytes, the first half
+    /// first.
+    #[inline]
+    pub const fn to_le_bytes(self) -> [u8; 16] {
+        ((self.1 as u128) << 64 | self.0 as u128).to_le_bytes()
+    }
+
+    /// Creates a fingerprint from the bytes returned by `to_le_bytes`.
+    #[inline]
+    pub const fn from_le_bytes(bytes: [u8; 16]) -> Fingerprint {
+        let value = u128::from_le_bytes(bytes);
+        Fingerprint(value as u64, (value >> 64) as u64)
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}{:016x}", self.0, self.1)
+    }
+}
+
+/// The error returned when parsing a `Fingerprint` from a string that is not
+/// made of exactly 32 hexadecimal digits.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseFingerprintError {
+    _priv: (),
+}
+
+impl fmt::Display for ParseFingerprintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a fingerprint must be made of 32 hexadecimal digits")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseFingerprintError {}
+
+impl FromStr for Fingerprint {
+    type Err = ParseFingerprintError;
+
+    fn from_str(s: &str) -> Result<Fingerprint, ParseFingerprintError> {
+        let error = ParseFingerprintError { _priv: () };
+        // `from_str_radix` also accepts a sign, which is not a digit.
+        if s.len() != 32 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(error);
+        }
+        let (first, second) = s.split_at(16);
+        match (
+            u64::from_str_radix(first, 16),
+            u64::from_str_radix(second, 16),
+        ) {
+            (Ok(first), Ok(second)) => Ok(Fingerprint(first, second)),
+            _ => Err(error),
+        }
+    }
+}
+
+/// A hasher whose results do not depend on the platform, for hashes that are
+/// persisted or compared across machines.
+///
+/// This is SipHash-1-3 with a 128-bit output and zero keys, as used by rustc
+/// for incremental compilation. Integers passed to the `write_*` methods are
+/// hashed as little-endian bytes, and `usize` and `isize` are always hashed
+/// as `u64` and `i64`. Bytes passed to `write` are hashed as they are.
+///
+/// The `Hash` implementations of `std` for slices and arrays of integers pass
+/// the memory of the whole slice to `write`, which holds the integers in the
+/// byte order of the target, so their fingerprints differ between little- and
+/// big-endian targets. `HashStable` hashes slices one element at a time, and
+/// a value hashed with it produces the same `Fingerprint` on every target; on
+/// little-endian targets, it is the same as with `Hash`. `StableHasher` is
+/// much slower than `FxHasher`, and is not meant for in-memory hash tables.
+///
+/// # Example
+///
+/// ```rust
+/// use rustc_hash::{Fingerprint, HashStable, StableHasher};
+/// use std::hash::{Hash, Hasher};
+///
+/// // These match the reference SipHash-1-3 with a 128-bit output and zero keys.
+/// let mut hasher = StableHasher::new();
+/// assert_eq!(
+///     hasher.finish128(),
+///     Fingerprint::new(0x2134935d61d9b40d, 0xb91c5ed031fb3303),
+/// );
+///
+/// hasher.write(b"rustc-hash");
+/// assert_eq!(
+///     hasher.finish128(),
+///     Fingerprint::new(0x2f78f5b1b8490c0c, 0x3d0be04244a866ca),
+/// );
+///
+/// // `usize` is hashed as `u64`, whatever the pointer width.
+/// let mut a = StableHasher::new();
+/// let mut b = StableHasher::new();
+/// a.write_usize(22);
+/// b.write_u64(22);
+/// assert_eq!(a.finish128(), b.finish128());
+///
+/// // This holds on every target.
+/// let value = (22u32, "x", [1u64, 2]);
+/// let mut hasher = StableHasher::new();
+/// value.hash_stable(&mut (), &mut hasher);
+/// let fingerprint = hasher.finish128();
+/// assert_eq!(
+///     fingerprint,
+///     Fingerprint::new(0x668a291355d35c9c, 0x1f99a7f4ed294247),
+/// );
+///
+/// // `Hash` writes `[1u64, 2]` in native byte order.
+/// let mut hasher = StableHasher::new();
+/// value.hash(&mut hasher);
+/// if cfg!(target_endian = "little") {
+///     assert_eq!(hasher.finish128(), fingerprint);
+/// } else {
+///     assert_ne!(hasher.finish128(), fingerprint);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct StableHasher {
+    v0: u64,
+    v1: u64,
+    v2: u64,
+    v3: u64,
+    // The bytes that do not yet form a whole word, in its low bytes.
+    tail: u64,
+    ntail: usize,
+    length: u64,
+}
+
+#[inline]
+fn sip_round(v0: &mut u64, v1: &mut u64, v2: &mut u64, v3: &mut u64) {
+    *v0 = v0.wrapping_add(*v1);
+    *v1 = v1.rotate_left(13);
+    *v1 ^= *v0;
+    *v0 = v0.rotate_left(32);
+    *v2 = v2.wrapping_add(*v3);
+    *v3 = v3.rotate_left(16);
+    *v3 ^= *v2;
+    *v0 = v0.wrapping_add(*v3);
+    *v3 = v3.rotate_left(21);
+    *v3 ^= *v0;
+    *v2 = v2.wrapping_add(*v1);
+    *v1 = v1.rotate_left(17);
+    *v1 ^= *v2;
+    *v2 = v2.rotate_left(32);
+}
+
+// Loads up to 8 bytes as a little-endian integer.
+#[inline]
+fn load_le(bytes: &[u8]) -> u64 {
+    let mut word = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        word |= (byte as u64) << (8 * i);
+    }
+    word
+}
+
+impl StableHasher {
+    /// Creates a new `StableHasher`.
+    #[inline]
+    pub const fn new() -> StableHasher {
+        StableHasher {
+            v0: 0x736f6d6570736575,
+            // The 128-bit variant of SipHash tweaks `v1`.
+            v1: 0x646f72616e646f6d ^ 0xee,
+            v2: 0x6c7967656e657261,
+            v3: 0x7465646279746573,
+            tail: 0,
+            ntail: 0,
+            length: 0,
+        }
+    }
+
+    #[inline]
+    fn compress(&mut self, m: u64) {
+        self.v3 ^= m;
+        sip_round(&mut self.v0, &mut self.v1, &mut self.v2, &mut self.v3);
+        self.v0 ^= m;
+    }
+
+    /// Returns the 128-bit hash of the values written so far.
+    pub fn finish128(&self) -> Fingerprint {
+        let (mut v0, mut v1, mut v2, mut v3) = (self.v0, self.v1, self.v2, self.v3);
+        let b = (self.length & 0xff) << 56 | self.tail;
+
+        v3 ^= b;
+        sip_round(&mut v0, &mut v1, &mut v2, &mut v3);
+        v0 ^= b;
+
+        v2 ^= 0xee;
+        for _ in 0..3 {
+            sip_round(&mut v0, &mut v1, &mut v2, &mut v3);
+        }
+        let first = v0 ^ v1 ^ v2 ^ v3;
+
+        v1 ^= 0xdd;
+        for _ in 0..3 {
+            sip_round(&mut v0, &mut v1, &mut v2, &mut v3);
+        }
+        let second = v0 ^ v1 ^ v2 ^ v3;
+
+        Fingerprint(first, second)
+    }
+}
+
+impl Default for StableHasher {
+    #[inline]
+    fn default() -> StableHasher {
+        StableHasher::new()
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, mut bytes: &[u8]) {
+        self.length = self.length.wrapping_add(bytes.len() as u64);
+
+        if self.ntail != 0 {
+            let needed = 8 - self.ntail;
+            let fill = needed.min(bytes.len());
+            self.tail |= load_le(&bytes[..fill]) << (8 * self.ntail);
+            if bytes.len() < needed {
+                self.ntail += bytes.len();
+                return;
+            }
+            let tail = self.tail;
+            self.compress(tail);
+            bytes = &bytes[needed..];
+        }
+
+        while bytes.len() >= 8 {
+            self.compress(load_le(&bytes[..8]));
+            bytes = &bytes[8..];
+        }
+        self.tail = load_le(bytes);
+        self.ntail = bytes.len();
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.write(&[i]);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64);
+    }
+
+    /// Returns the first half of `finish128`.
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.finish128().0
+    }
+}
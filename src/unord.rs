@@ -0,0 +1,621 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::iter::{FromIterator, Sum};
+use core::ops::Index;
+
+#[cfg(all(not(feature = "std"), feature = "hashbrown"))]
+use hashbrown::{hash_map, hash_set};
+#[cfg(feature = "std")]
+use std::collections::{hash_map, hash_set};
+
+use crate::{hash_unordered_map, hash_unordered_set, FxHashMap, FxHashSet};
+
+/// A view of the elements of an `FxUnordMap` or `FxUnordSet` that only offers
+/// operations whose result does not depend on the iteration order.
+///
+/// This is deliberately not an `Iterator`: the elements can be transformed
+/// with `map`, `filter` and `filter_map`, tested with `all` and `any`,
+/// counted, summed, reduced to their minimum or maximum, folded with a
+/// commutative and associative operation, collected into another unordered
+/// collection, or sorted into a `Vec`.
+pub struct UnordItems<I>(I);
+
+impl<I: Iterator> UnordItems<I> {
+    /// Transforms every element.
+    #[inline]
+    pub fn map<U, F: FnMut(I::Item) -> U>(self, f: F) -> UnordItems<core::iter::Map<I, F>> {
+        UnordItems(self.0.map(f))
+    }
+
+    /// Keeps the elements for which `f` returns `true`.
+    #[inline]
+    pub fn filter<F: FnMut(&I::Item) -> bool>(self, f: F) -> UnordItems<core::iter::Filter<I, F>> {
+        UnordItems(self.0.filter(f))
+    }
+
+    /// Transforms every element, keeping only the `Some` results.
+    #[inline]
+    pub fn filter_map<U, F: FnMut(I::Item) -> Option<U>>(
+        self,
+        f: F,
+    ) -> UnordItems<core::iter::FilterMap<I, F>> {
+        UnordItems(self.0.filter_map(f))
+    }
+
+    /// Returns `true` if `f` returns `true` for every element.
+    #[inline]
+    pub fn all<F: FnMut(I::Item) -> bool>(mut self, f: F) -> bool {
+        self.0.all(f)
+    }
+
+    /// Returns `true` if `f` returns `true` for any element.
+    #[inline]
+    pub fn any<F: FnMut(I::Item) -> bool>(mut self, f: F) -> bool {
+        self.0.any(f)
+    }
+
+    /// Returns the number of elements.
+    #[inline]
+    pub fn count(self) -> usize {
+        self.0.count()
+    }
+
+    /// Sums the elements.
+    ///
+    /// The result is required to be `Ord`, which rules out floating-point
+    /// numbers, whose sum is rounded differently depending on the order of
+    /// the elements. Integer sums can still overflow, and with overflow
+    /// checks, as in debug builds, whether they panic can depend on the
+    /// order when the elements have different signs.
+    #[inline]
+    pub fn sum<S>(self) -> S
+    where
+        S: Sum<I::Item> + Ord,
+    {
+        self.0.sum()
+    }
+
+    /// Returns the smallest element, or `None` if there are none.
+    ///
+    /// Of several equal elements, which one is returned is unspecified.
+    #[inline]
+    pub fn min(self) -> Option<I::Item>
+    where
+        I::Item: Ord,
+    {
+        self.0.min()
+    }
+
+    /// Returns the largest element, or `None` if there are none.
+    ///
+    /// Of several equal elements, which one is returned is unspecified.
+    #[inline]
+    pub fn max(self) -> Option<I::Item>
+    where
+        I::Item: Ord,
+    {
+        self.0.max()
+    }
+
+    /// Folds every element into an accumulator.
+    ///
+    /// The elements are visited in an arbitrary order, so the result is only
+    /// reproducible if `f(f(acc, a), b)` always equals `f(f(acc, b), a)`. This
+    /// holds when `f` applies an operation that is commutative and
+    /// associative, such as a wrapping addition, a bitwise or, or an
+    /// insertion into a set. It is not checked.
+    #[inline]
+    pub fn fold<B, F: FnMut(B, I::Item) -> B>(self, init: B, f: F) -> B {
+        self.0.fold(init, f)
+    }
+
+    /// Collects the elements into a `Vec`, sorted.
+    #[inline]
+    pub fn into_sorted_vec(self) -> Vec<I::Item>
+    where
+        I::Item: Ord,
+    {
+        let mut items: Vec<I::Item> = self.0.collect();
+        items.sort_unstable();
+        items
+    }
+}
+
+impl<I> fmt::Debug for UnordItems<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("UnordItems { .. }")
+    }
+}
+
+/// Writes a string as it is, for entries formatted in advance.
+struct Formatted<'a>(&'a str);
+
+impl fmt::Debug for Formatted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+/// Formats `value` with the same flag for pretty-printing as `f`.
+fn format_debug<T: fmt::Debug>(f: &fmt::Formatter<'_>, value: T) -> String {
+    if f.alternate() {
+        alloc::format!("{:#?}", value)
+    } else {
+        alloc::format!("{:?}", value)
+    }
+}
+
+/// A hash map using the `fx` hash algorithm whose API does not depend on the
+/// iteration order of the underlying `FxHashMap`.
+///
+/// The iteration order of an `FxHashMap` is deterministic but arbitrary, and
+/// changes with the capacity and the insertion history of the map, so code
+/// that iterates over one can produce different results for maps with the
+/// same contents. `FxUnordMap` offers lookups and updates, but its entries
+/// can only be reached through `items`, `keys` and `values`, which return an
+/// `UnordItems` view, or through `to_sorted_vec`. Depending on the iteration
+/// order is then a compile error rather than a reproducibility bug.
+///
+/// Its `Hash` implementation uses `hash_unordered_map`.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "std")]
+/// # fn main() {
+/// use rustc_hash::FxUnordMap;
+///
+/// let mut map = FxUnordMap::new();
+/// map.insert("b", 2);
+/// map.insert("a", 1);
+/// map.insert("c", 3);
+///
+/// assert_eq!(map["a"], 1);
+/// assert_eq!(map.values().sum::<i32>(), 6);
+/// assert_eq!(map.keys().max(), Some(&"c"));
+/// assert!(map.keys().all(|k| k.len() == 1));
+/// assert_eq!(format!("{:?}", map), r#"{"a": 1, "b": 2, "c": 3}"#);
+/// assert_eq!(map.to_sorted_vec(), [(&"a", &1), (&"b", &2), (&"c", &3)]);
+///
+/// let squares: FxUnordMap<&str, i32> = map.items().map(|(k, v)| (*k, v * v)).into();
+/// assert_eq!(squares["c"], 9);
+/// # }
+/// # #[cfg(not(feature = "std"))]
+/// # fn main() { }
+/// ```
+///
+/// Iterating directly does not compile:
+///
+/// ```compile_fail
+/// use rustc_hash::FxUnordMap;
+///
+/// let map: FxUnordMap<u32, u32> = FxUnordMap::new();
+/// for (k, v) in map.items() {}
+/// ```
+#[derive(Clone)]
+pub struct FxUnordMap<K, V> {
+    inner: FxHashMap<K, V>,
+}
+
+impl<K, V> FxUnordMap<K, V> {
+    /// Creates an empty map.
+    #[inline]
+    pub fn new() -> FxUnordMap<K, V> {
+        FxUnordMap {
+            inner: FxHashMap::default(),
+        }
+    }
+
+    /// Creates an empty map with space for at least `capacity` entries.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> FxUnordMap<K, V> {
+        FxUnordMap {
+            inner: FxHashMap::with_capacity_and_hasher(capacity, Default::default()),
+        }
+    }
+
+    /// Returns the number of entries in the map.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns `true` if the map contains no entries.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Removes all the entries.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    /// Returns a view of the entries.
+    #[inline]
+    pub fn items(&self) -> UnordItems<hash_map::Iter<'_, K, V>> {
+        UnordItems(self.inner.iter())
+    }
+
+    /// Returns a view of the entries, consuming the map.
+    #[inline]
+    pub fn into_items(self) -> UnordItems<hash_map::IntoIter<K, V>> {
+        UnordItems(self.inner.into_iter())
+    }
+
+    /// Returns a view of the keys.
+    #[inline]
+    pub fn keys(&self) -> UnordItems<hash_map::Keys<'_, K, V>> {
+        UnordItems(self.inner.keys())
+    }
+
+    /// Returns a view of the values.
+    #[inline]
+    pub fn values(&self) -> UnordItems<hash_map::Values<'_, K, V>> {
+        UnordItems(self.inner.values())
+    }
+
+    /// Returns the entries, sorted by key.
+    pub fn to_sorted_vec(&self) -> Vec<(&K, &V)>
+    where
+        K: Ord,
+    {
+        let mut items: Vec<(&K, &V)> = self.inner.iter().collect();
+        items.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        items
+    }
+
+    /// Returns the entries sorted by key, consuming the map.
+    pub fn into_sorted_vec(self) -> Vec<(K, V)>
+    where
+        K: Ord,
+    {
+        let mut items: Vec<(K, V)> = self.inner.into_iter().collect();
+        items.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        items
+    }
+}
+
+impl<K: Eq + Hash, V> FxUnordMap<K, V> {
+    /// Inserts a key-value pair, returning the previous value of the key.
+    #[inline]
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.inner.insert(key, value)
+    }
+
+    /// Removes a key, returning its value.
+    #[inline]
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.remove(key)
+    }
+
+    /// Returns a reference to the value corresponding to `key`.
+    #[inline]
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.get(key)
+    }
+
+    /// Returns a mutable reference to the value corresponding to `key`.
+    #[inline]
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.get_mut(key)
+    }
+
+    /// Returns `true` if the map contains a value for `key`.
+    #[inline]
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.contains_key(key)
+    }
+
+    /// Keeps only the entries for which `f` returns `true`.
+    ///
+    /// `f` is called on the entries in an arbitrary order.
+    #[inline]
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, f: F) {
+        self.inner.retain(f);
+    }
+}
+
+impl<K, V> Default for FxUnordMap<K, V> {
+    #[inline]
+    fn default() -> FxUnordMap<K, V> {
+        FxUnordMap::new()
+    }
+}
+
+impl<K: Eq + Hash, V: PartialEq> PartialEq for FxUnordMap<K, V> {
+    #[inline]
+    fn eq(&self, other: &FxUnordMap<K, V>) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<K: Eq + Hash, V: Eq> Eq for FxUnordMap<K, V> {}
+
+impl<K: Hash, V: Hash> Hash for FxUnordMap<K, V> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(hash_unordered_map(&self.inner));
+    }
+}
+
+/// The entries are sorted by their formatted keys and values, so that the
+/// output does not depend on the iteration order.
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for FxUnordMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut entries: Vec<(String, String)> = self
+            .inner
+            .iter()
+            .map(|(k, v)| (format_debug(f, k), format_debug(f, v)))
+            .collect();
+        entries.sort_unstable();
+        f.debug_map()
+            .entries(entries.iter().map(|(k, v)| (Formatted(k), Formatted(v))))
+            .finish()
+    }
+}
+
+impl<K, Q, V> Index<&Q> for FxUnordMap<K, V>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: Eq + Hash + ?Sized,
+{
+    type Output = V;
+
+    #[inline]
+    fn index(&self, key: &Q) -> &V {
+        &self.inner[key]
+    }
+}
+
+impl<K, V> From<FxHashMap<K, V>> for FxUnordMap<K, V> {
+    #[inline]
+    fn from(inner: FxHashMap<K, V>) -> FxUnordMap<K, V> {
+        FxUnordMap { inner }
+    }
+}
+
+impl<K: Eq + Hash, V, I: Iterator<Item = (K, V)>> From<UnordItems<I>> for FxUnordMap<K, V> {
+    #[inline]
+    fn from(items: UnordItems<I>) -> FxUnordMap<K, V> {
+        FxUnordMap {
+            inner: items.0.collect(),
+        }
+    }
+}
+
+impl<K: Eq + Hash, V> FromIterator<(K, V)> for FxUnordMap<K, V> {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> FxUnordMap<K, V> {
+        FxUnordMap {
+            inner: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<K: Eq + Hash, V> Extend<(K, V)> for FxUnordMap<K, V> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        self.inner.extend(iter);
+    }
+}
+
+/// A hash set using the `fx` hash algorithm whose API does not depend on the
+/// iteration order of the underlying `FxHashSet`.
+///
+/// This is the set counterpart of `FxUnordMap`: its elements can only be
+/// reached through `items`, which returns an `UnordItems` view, or through
+/// `to_sorted_vec`. Its `Hash` implementation uses `hash_unordered_set`.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "std")]
+/// # fn main() {
+/// use rustc_hash::FxUnordSet;
+///
+/// let set: FxUnordSet<u32> = [3, 1, 2].iter().cloned().collect();
+/// assert!(set.contains(&2));
+/// assert_eq!(set.items().sum::<u32>(), 6);
+/// assert_eq!(set.items().min(), Some(&1));
+/// assert_eq!(set.items().fold(0, |bits, x| bits | 1 << x), 0b1110);
+/// assert_eq!(format!("{:?}", set), "{1, 2, 3}");
+/// assert_eq!(set.to_sorted_vec(), [&1, &2, &3]);
+///
+/// let odd: FxUnordSet<u32> = set.items().filter(|x| *x % 2 == 1).map(|x| *x).into();
+/// assert_eq!(odd.into_sorted_vec(), [1, 3]);
+/// # }
+/// # #[cfg(not(feature = "std"))]
+/// # fn main() { }
+/// ```
+#[derive(Clone)]
+pub struct FxUnordSet<T> {
+    inner: FxHashSet<T>,
+}
+
+impl<T> FxUnordSet<T> {
+    /// Creates an empty set.
+    #[inline]
+    pub fn new() -> FxUnordSet<T> {
+        FxUnordSet {
+            inner: FxHashSet::default(),
+        }
+    }
+
+    /// Creates an empty set with space for at least `capacity` elements.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> FxUnordSet<T> {
+        FxUnordSet {
+            inner: FxHashSet::with_capacity_and_hasher(capacity, Default::default()),
+        }
+    }
+
+    /// Returns the number of elements in the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns `true` if the set contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Removes all the elements.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    /// Returns a view of the elements.
+    #[inline]
+    pub fn items(&self) -> UnordItems<hash_set::Iter<'_, T>> {
+        UnordItems(self.inner.iter())
+    }
+
+    /// Returns a view of the elements, consuming the set.
+    #[inline]
+    pub fn into_items(self) -> UnordItems<hash_set::IntoIter<T>> {
+        UnordItems(self.inner.into_iter())
+    }
+
+    /// Returns the elements, sorted.
+    pub fn to_sorted_vec(&self) -> Vec<&T>
+    where
+        T: Ord,
+    {
+        self.items().into_sorted_vec()
+    }
+
+    /// Returns the elements sorted, consuming the set.
+    pub fn into_sorted_vec(self) -> Vec<T>
+    where
+        T: Ord,
+    {
+        self.into_items().into_sorted_vec()
+    }
+}
+
+impl<T: Eq + Hash> FxUnordSet<T> {
+    /// Adds an element, returning `true` if it was not present.
+    #[inline]
+    pub fn insert(&mut self, value: T) -> bool {
+        self.inner.insert(value)
+    }
+
+    /// Removes an element, returning `true` if it was present.
+    #[inline]
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.remove(value)
+    }
+
+    /// Returns `true` if the set contains `value`.
+    #[inline]
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.contains(value)
+    }
+
+    /// Keeps only the elements for which `f` returns `true`.
+    ///
+    /// `f` is called on the elements in an arbitrary order.
+    #[inline]
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, f: F) {
+        self.inner.retain(f);
+    }
+}
+
+impl<T> Default for FxUnordSet<T> {
+    #[inline]
+    fn default() -> FxUnordSet<T> {
+        FxUnordSet::new()
+    }
+}
+
+impl<T: Eq + Hash> PartialEq for FxUnordSet<T> {
+    #[inline]
+    fn eq(&self, other: &FxUnordSet<T>) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<T: Eq + Hash> Eq for FxUnordSet<T> {}
+
+impl<T: Hash> Hash for FxUnordSet<T> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(hash_unordered_set(&self.inner));
+    }
+}
+
+/// The elements are sorted by their formatted values, so that the output
+/// does not depend on the iteration order.
+impl<T: fmt::Debug> fmt::Debug for FxUnordSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut elements: Vec<String> = self.inner.iter().map(|x| format_debug(f, x)).collect();
+        elements.sort_unstable();
+        f.debug_set()
+            .entries(elements.iter().map(|x| Formatted(x)))
+            .finish()
+    }
+}
+
+impl<T> From<FxHashSet<T>> for FxUnordSet<T> {
+    #[inline]
+    fn from(inner: FxHashSet<T>) -> FxUnordSet<T> {
+        FxUnordSet { inner }
+    }
+}
+
+impl<T: Eq + Hash, I: Iterator<Item = T>> From<UnordItems<I>> for FxUnordSet<T> {
+    #[inline]
+    fn from(items: UnordItems<I>) -> FxUnordSet<T> {
+        FxUnordSet {
+            inner: items.0.collect(),
+        }
+    }
+}
+
+impl<T: Eq + Hash> FromIterator<T> for FxUnordSet<T> {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> FxUnordSet<T> {
+        FxUnordSet {
+            inner: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<T: Eq + Hash> Extend<T> for FxUnordSet<T> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.inner.extend(iter);
+    }
+}
@@ -0,0 +1,240 @@
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+use core::hash::BuildHasher;
+use core::hash::{Hash, Hasher};
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+use core::ops::{Deref, DerefMut};
+
+use crate::mix::folded_multiply;
+use crate::FxHasher64;
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+use crate::{FxBuildHasher, HashMap, HashSet};
+
+// Constants of the mixing of each element and of the final digest, the
+// digits of pi and e.
+const MIX_XOR: u64 = 0x243f6a8885a308d3;
+const MIX_MUL: u64 = 0xb7e151628aed2a6b;
+
+/// Calculates a digest of the elements yielded by `iter` that does not depend
+/// on their order.
+///
+/// Each element is hashed with `FxHasher64` and mixed with a folded multiply,
+/// so that elements cannot cancel each other out. The mixed hashes are then
+/// combined with a wrapping sum and an exclusive or, which are commutative,
+/// and the digest is the mix of these two values and of the number of
+/// elements.
+///
+/// Unlike the default `FxHasher`, `FxHasher64` gives the same hashes on every
+/// target and does not change between releases of this crate, so digests can
+/// be compared between processes and machines. They still depend on the
+/// `Hash` implementations of the elements: those of `std` for slices and
+/// arrays of integers write them in the byte order of the target.
+///
+/// # Example
+///
+/// ```rust
+/// use rustc_hash::hash_unordered;
+///
+/// assert_eq!(hash_unordered(&[1, 2, 3]), hash_unordered(&[3, 1, 2]));
+/// assert_ne!(hash_unordered(&[1, 2, 3]), hash_unordered(&[1, 2]));
+/// // Unlike an exclusive or of the hashes, pairs do not cancel out.
+/// assert_ne!(hash_unordered(&[1, 1]), hash_unordered(&[2, 2]));
+///
+/// // This holds on every target.
+/// assert_eq!(hash_unordered(&[1u32, 2, 3]), 0xa7463510f89720ff);
+/// ```
+pub fn hash_unordered<I>(iter: I) -> u64
+where
+    I: IntoIterator,
+    I::Item: Hash,
+{
+    let (mut sum, mut xor, mut count) = (0u64, 0u64, 0u64);
+    for value in iter {
+        let mut hasher = FxHasher64::default();
+        value.hash(&mut hasher);
+        let hash = folded_multiply(hasher.finish() ^ MIX_XOR, MIX_MUL);
+        sum = sum.wrapping_add(hash);
+        xor ^= hash;
+        count += 1;
+    }
+    let mut hasher = FxHasher64::default();
+    hasher.write_u64(sum);
+    hasher.write_u64(xor);
+    hasher.write_u64(count);
+    folded_multiply(hasher.finish() ^ MIX_XOR, MIX_MUL)
+}
+
+/// Calculates a digest of the entries of a map that does not depend on their
+/// iteration order.
+///
+/// Two maps with equal contents have the same digest, whatever their
+/// insertion history, capacity or hasher. This is `hash_unordered` of the
+/// `(key, value)` pairs.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "std")]
+/// # fn main() {
+/// use rustc_hash::{hash_unordered_map, FxHashMap};
+///
+/// let mut a = FxHashMap::default();
+/// let mut b = FxHashMap::with_capacity_and_hasher(1000, Default::default());
+/// for i in 0..100 {
+///     a.insert(i, i * 2);
+///     b.insert(99 - i, (99 - i) * 2);
+/// }
+/// assert_eq!(hash_unordered_map(&a), hash_unordered_map(&b));
+///
+/// b.insert(0, 1);
+/// assert_ne!(hash_unordered_map(&a), hash_unordered_map(&b));
+/// # }
+/// # #[cfg(not(feature = "std"))]
+/// # fn main() { }
+/// ```
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub fn hash_unordered_map<K: Hash, V: Hash, S>(map: &HashMap<K, V, S>) -> u64 {
+    hash_unordered(map.iter())
+}
+
+/// Calculates a digest of the elements of a set that does not depend on their
+/// iteration order.
+///
+/// Two sets with equal contents have the same digest, whatever their
+/// insertion history, capacity or hasher.
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+pub fn hash_unordered_set<T: Hash, S>(set: &HashSet<T, S>) -> u64 {
+    hash_unordered(set.iter())
+}
+
+/// A map that implements `Hash` with `hash_unordered_map`, so that it can be
+/// hashed or used as a key itself.
+///
+/// It dereferences to the wrapped map.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "std")]
+/// # fn main() {
+/// use rustc_hash::{FxHashSet, UnorderedMap};
+///
+/// let mut a: UnorderedMap<&str, u32> = UnorderedMap::default();
+/// a.insert("x", 1);
+/// a.insert("y", 2);
+/// let mut b: UnorderedMap<&str, u32> = UnorderedMap::default();
+/// b.insert("y", 2);
+/// b.insert("x", 1);
+///
+/// let mut seen = FxHashSet::default();
+/// seen.insert(a);
+/// assert!(seen.contains(&b));
+/// # }
+/// # #[cfg(not(feature = "std"))]
+/// # fn main() { }
+/// ```
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+#[derive(Clone, Debug, Default)]
+pub struct UnorderedMap<K, V, S = FxBuildHasher>(pub HashMap<K, V, S>);
+
+/// A set that implements `Hash` with `hash_unordered_set`, so that it can be
+/// hashed or used as a key itself.
+///
+/// It dereferences to the wrapped set.
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+#[derive(Clone, Debug, Default)]
+pub struct UnorderedSet<T, S = FxBuildHasher>(pub HashSet<T, S>);
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<K: Hash, V: Hash, S> Hash for UnorderedMap<K, V, S> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(hash_unordered_map(&self.0));
+    }
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<T: Hash, S> Hash for UnorderedSet<T, S> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(hash_unordered_set(&self.0));
+    }
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<K, V, S> PartialEq for UnorderedMap<K, V, S>
+where
+    K: Eq + Hash,
+    V: PartialEq,
+    S: BuildHasher,
+{
+    #[inline]
+    fn eq(&self, other: &UnorderedMap<K, V, S>) -> bool {
+        self.0 == other.0
+    }
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<K: Eq + Hash, V: Eq, S: BuildHasher> Eq for UnorderedMap<K, V, S> {}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<T: Eq + Hash, S: BuildHasher> PartialEq for UnorderedSet<T, S> {
+    #[inline]
+    fn eq(&self, other: &UnorderedSet<T, S>) -> bool {
+        self.0 == other.0
+    }
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<T: Eq + Hash, S: BuildHasher> Eq for UnorderedSet<T, S> {}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<K, V, S> Deref for UnorderedMap<K, V, S> {
+    type Target = HashMap<K, V, S>;
+
+    #[inline]
+    fn deref(&self) -> &HashMap<K, V, S> {
+        &self.0
+    }
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<K, V, S> DerefMut for UnorderedMap<K, V, S> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut HashMap<K, V, S> {
+        &mut self.0
+    }
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<T, S> Deref for UnorderedSet<T, S> {
+    type Target = HashSet<T, S>;
+
+    #[inline]
+    fn deref(&self) -> &HashSet<T, S> {
+        &self.0
+    }
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<T, S> DerefMut for UnorderedSet<T, S> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut HashSet<T, S> {
+        &mut self.0
+    }
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<K, V, S> From<HashMap<K, V, S>> for UnorderedMap<K, V, S> {
+    #[inline]
+    fn from(map: HashMap<K, V, S>) -> UnorderedMap<K, V, S> {
+        UnorderedMap(map)
+    }
+}
+
+#[cfg(any(feature = "std", feature = "hashbrown"))]
+impl<T, S> From<HashSet<T, S>> for UnorderedSet<T, S> {
+    #[inline]
+    fn from(set: HashSet<T, S>) -> UnorderedSet<T, S> {
+        UnorderedSet(set)
+    }
+}
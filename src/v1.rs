@@ -0,0 +1,357 @@
+//! Version 1 of the `fx` algorithm.
+//!
+//! This is the algorithm `FxHasher` has implemented since the first release of
+//! this crate. The hashes produced by the types in this module are frozen:
+//! they will stay the same in every future release, so they can be persisted.
+//!
+//! # Example
+//!
+//! These values are checked by the test suite on every target.
+//!
+//! ```rust
+//! use std::hash::{Hash, Hasher};
+//! use rustc_hash::v1::FxHasher;
+//!
+//! fn fx(hasher: FxHasher, f: impl Fn(&mut FxHasher)) -> u64 {
+//!     let mut hasher = hasher;
+//!     f(&mut hasher);
+//!     hasher.finish()
+//! }
+//!
+//! let ints = [
+//!     fx(FxHasher::default(), |h| h.write_u8(0xab)),
+//!     fx(FxHasher::default(), |h| h.write_u16(0xabcd)),
+//!     fx(FxHasher::default(), |h| h.write_u32(0x12345678)),
+//!     fx(FxHasher::default(), |h| h.write_u64(0x0123456789abcdef)),
+//!     fx(FxHasher::default(), |h| h.write_usize(12345)),
+//!     fx(FxHasher::with_seed(42), |h| h.write_u64(7)),
+//!     fx(FxHasher::new_nonzero(), |h| h.write_u64(7)),
+//! ];
+//! #[cfg(target_pointer_width = "64")]
+//! let expected = [
+//!     0x6e55655723bd1187,
+//!     0x964c76ce13540051,
+//!     0x5582aca867c703d8,
+//!     0x56cc4aad99c8321b,
+//!     0x8919791e18904b2d,
+//!     0x09624d8f84a5d853,
+//!     0xf34015745db2399f,
+//! ];
+//! #[cfg(target_pointer_width = "32")]
+//! let expected = [
+//!     0xaf0e4e93, 0xc0bb0c25, 0x887934b8, 0x29d11f01, 0xa12cca31, 0x7127268e, 0x353c84d4,
+//! ];
+//! assert_eq!(ints, expected);
+//!
+//! // Byte-oriented input is read in native endianness.
+//! if cfg!(target_endian = "little") {
+//!     let bytes = [
+//!         fx(FxHasher::default(), |h| h.write_u128(0x0123456789abcdeffedcba9876543210)),
+//!         fx(FxHasher::default(), |h| h.write(b"hello world!")),
+//!         fx(FxHasher::default(), |h| h.write(&[1, 2, 3, 4, 5, 6, 7])),
+//!         fx(FxHasher::default(), |h| "rustc".hash(h)),
+//!         fx(FxHasher::default(), |h| (1u32, 2u64, "x").hash(h)),
+//!     ];
+//!     #[cfg(target_pointer_width = "64")]
+//!     let expected = [
+//!         0x9b6753504669aa49,
+//!         0x1a4f0af733454230,
+//!         0xb72b4fe2838adfe1,
+//!         0xe6dfb73002edbb7d,
+//!         0x7b3da4b83335d8d3,
+//!     ];
+//!     #[cfg(target_pointer_width = "32")]
+//!     let expected = [0xce57d7ea, 0x128d0c12, 0xf5d01727, 0x790d2097, 0x6f5700ad];
+//!     assert_eq!(bytes, expected);
+//! }
+//! ```
+
+use core::default::Default;
+use core::hash::Hasher;
+use core::mem::size_of;
+
+pub use crate::fixed_width::{FxHasher32, FxHasher64};
+pub use crate::length_aware::FxLengthAwareHasher;
+
+/// A speedy hash algorithm for use within rustc. The hashmap in liballoc
+/// by default uses SipHash which isn't quite as speedy as we want. In the
+/// compiler we're not really worried about DOS attempts, so we use a fast
+/// non-cryptographic hash.
+///
+/// This is the same as the algorithm used by Firefox -- which is a homespun
+/// one not based on any widely-known algorithm -- though modified to produce
+/// 64-bit hash values instead of 32-bit hash values. It consistently
+/// out-performs an FNV-based hash within rustc itself -- the collision rate is
+/// similar or slightly worse than FNV, but the speed of the hash function
+/// itself is much higher because it works on up to 8 bytes at a time.
+#[derive(Clone, Debug)]
+pub struct FxHasher {
+    pub(crate) hash: usize,
+}
+
+#[cfg(target_pointer_width = "32")]
+pub(crate) const K: usize = 0x9e3779b9;
+#[cfg(target_pointer_width = "64")]
+pub(crate) const K: usize = 0x517cc1b727220a95;
+
+// The leading digits of the fractional part of pi, used as the starting state
+// of `FxHasher::new_nonzero`.
+#[cfg(target_pointer_width = "32")]
+const NONZERO_STATE: usize = 0x243f6a88;
+#[cfg(target_pointer_width = "64")]
+const NONZERO_STATE: usize = 0x243f6a8885a308d3;
+
+impl Default for FxHasher {
+    #[inline]
+    fn default() -> FxHasher {
+        FxHasher::new()
+    }
+}
+
+#[inline]
+pub(crate) const fn read_u16(bytes: &[u8]) -> u16 {
+    u16::from_ne_bytes([bytes[0], bytes[1]])
+}
+
+#[inline]
+pub(crate) const fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+#[cfg(target_pointer_width = "64")]
+#[inline]
+pub(crate) const fn read_usize(bytes: &[u8]) -> usize {
+    let b = bytes;
+    u64::from_ne_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as usize
+}
+
+#[cfg(target_pointer_width = "32")]
+#[inline]
+pub(crate) const fn read_usize(bytes: &[u8]) -> usize {
+    read_u32(bytes) as usize
+}
+
+impl FxHasher {
+    /// Creates a `FxHasher` starting from a zero state, like
+    /// `FxHasher::default()`.
+    ///
+    /// `FxHasher` also has `const` versions of the methods of `Hasher`, which
+    /// the `Hasher` implementation forwards to, so hashes computed at compile
+    /// time are always the same as those computed at runtime.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::hash::Hasher;
+    /// use rustc_hash::FxHasher;
+    ///
+    /// const HASH: u64 = {
+    ///     let mut hasher = FxHasher::new();
+    ///     hasher.write_u64(22);
+    ///     hasher.write_bytes(b"hello world!");
+    ///     hasher.finish()
+    /// };
+    ///
+    /// let mut hasher = FxHasher::default();
+    /// Hasher::write_u64(&mut hasher, 22);
+    /// Hasher::write(&mut hasher, b"hello world!");
+    /// assert_eq!(Hasher::finish(&hasher), HASH);
+    /// ```
+    #[inline]
+    pub const fn new() -> FxHasher {
+        FxHasher { hash: 0 }
+    }
+
+    /// Creates a `FxHasher` whose state starts from `seed` instead of zero.
+    ///
+    /// Only the low 32 bits of the seed are used on 32-bit targets.
+    #[inline]
+    pub const fn with_seed(seed: u64) -> FxHasher {
+        FxHasher {
+            hash: seed as usize,
+        }
+    }
+
+    /// Creates a `FxHasher` starting from a fixed non-zero state.
+    ///
+    /// A default `FxHasher` starts at zero, and adding a zero word to a zero
+    /// state leaves it at zero. Any number of leading zero words is therefore
+    /// absorbed without a trace, so `(0u64, x)`, `(0u64, 0u64, x)` and `x` all
+    /// hash the same. From a non-zero state every zero word moves the state,
+    /// which removes this class of collisions.
+    ///
+    /// The hashes produced are different from those of `FxHasher::default()`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::hash::{Hash, Hasher};
+    /// use rustc_hash::FxHasher;
+    ///
+    /// fn hash<T: Hash>(mut hasher: FxHasher, value: T) -> u64 {
+    ///     value.hash(&mut hasher);
+    ///     hasher.finish()
+    /// }
+    ///
+    /// let x = 0x1234_5678u64;
+    /// assert_eq!(hash(FxHasher::default(), (0u64, x)), hash(FxHasher::default(), x));
+    /// assert_eq!(hash(FxHasher::default(), (0u64, 0u64, x)), hash(FxHasher::default(), x));
+    ///
+    /// assert_ne!(hash(FxHasher::new_nonzero(), (0u64, x)), hash(FxHasher::new_nonzero(), x));
+    /// assert_ne!(
+    ///     hash(FxHasher::new_nonzero(), (0u64, 0u64, x)),
+    ///     hash(FxHasher::new_nonzero(), (0u64, x))
+    /// );
+    ///
+    /// // No run of up to a thousand leading zero words collides with another.
+    /// let mut seen = std::collections::HashSet::new();
+    /// for zeros in 0..1000 {
+    ///     let mut hasher = FxHasher::new_nonzero();
+    ///     for _ in 0..zeros {
+    ///         hasher.write_u64(0);
+    ///     }
+    ///     hasher.write_u64(x);
+    ///     assert!(seen.insert(hasher.finish()));
+    /// }
+    /// ```
+    #[inline]
+    pub const fn new_nonzero() -> FxHasher {
+        FxHasher {
+            hash: NONZERO_STATE,
+        }
+    }
+
+    #[inline]
+    pub(crate) const fn add_to_hash(&mut self, i: usize) {
+        self.hash = (self.hash.rotate_left(5) ^ i).wrapping_mul(K);
+    }
+
+    /// Adds a byte buffer to the hash, like `Hasher::write`.
+    #[inline]
+    pub const fn write_bytes(&mut self, bytes: &[u8]) {
+        let mut bytes = bytes;
+        let mut hash = FxHasher { hash: self.hash };
+        assert!(size_of::<usize>() <= 8);
+        while bytes.len() >= size_of::<usize>() {
+            hash.add_to_hash(read_usize(bytes));
+            bytes = bytes.split_at(size_of::<usize>()).1;
+        }
+        if (size_of::<usize>() > 4) && (bytes.len() >= 4) {
+            hash.add_to_hash(read_u32(bytes) as usize);
+            bytes = bytes.split_at(4).1;
+        }
+        if (size_of::<usize>() > 2) && bytes.len() >= 2 {
+            hash.add_to_hash(read_u16(bytes) as usize);
+            bytes = bytes.split_at(2).1;
+        }
+        if (size_of::<usize>() > 1) && !bytes.is_empty() {
+            hash.add_to_hash(bytes[0] as usize);
+        }
+        self.hash = hash.hash;
+    }
+
+    /// Adds a `u8` to the hash, like `Hasher::write_u8`.
+    #[inline]
+    pub const fn write_u8(&mut self, i: u8) {
+        self.add_to_hash(i as usize);
+    }
+
+    /// Adds a `u16` to the hash, like `Hasher::write_u16`.
+    #[inline]
+    pub const fn write_u16(&mut self, i: u16) {
+        self.add_to_hash(i as usize);
+    }
+
+    /// Adds a `u32` to the hash, like `Hasher::write_u32`.
+    #[inline]
+    pub const fn write_u32(&mut self, i: u32) {
+        self.add_to_hash(i as usize);
+    }
+
+    /// Adds a `u64` to the hash, like `Hasher::write_u64`.
+    #[cfg(target_pointer_width = "32")]
+    #[inline]
+    pub const fn write_u64(&mut self, i: u64) {
+        self.add_to_hash(i as usize);
+        self.add_to_hash((i >> 32) as usize);
+    }
+
+    /// Adds a `u64` to the hash, like `Hasher::write_u64`.
+    #[cfg(target_pointer_width = "64")]
+    #[inline]
+    pub const fn write_u64(&mut self, i: u64) {
+        self.add_to_hash(i as usize);
+    }
+
+    /// Adds a `u128` to the hash, like `Hasher::write_u128`.
+    #[inline]
+    pub const fn write_u128(&mut self, i: u128) {
+        // Add the words in memory order, so that the hashes are the same as
+        // those of the `write` path `u128` used to go through.
+        let words = size_of::<u128>() / size_of::<usize>();
+        let mut n = 0;
+        while n < words {
+            #[cfg(target_endian = "little")]
+            let shift = n * size_of::<usize>() * 8;
+            #[cfg(target_endian = "big")]
+            let shift = (words - 1 - n) * size_of::<usize>() * 8;
+            self.add_to_hash((i >> shift) as usize);
+            n += 1;
+        }
+    }
+
+    /// Adds a `usize` to the hash, like `Hasher::write_usize`.
+    #[inline]
+    pub const fn write_usize(&mut self, i: usize) {
+        self.add_to_hash(i);
+    }
+
+    /// Returns the hash value for the values written so far, like
+    /// `Hasher::finish`.
+    #[inline]
+    pub const fn finish(&self) -> u64 {
+        self.hash as u64
+    }
+}
+
+impl Hasher for FxHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        self.write_bytes(bytes);
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        FxHasher::write_u8(self, i);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        FxHasher::write_u16(self, i);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        FxHasher::write_u32(self, i);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        FxHasher::write_u64(self, i);
+    }
+
+    #[inline]
+    fn write_u128(&mut self, i: u128) {
+        FxHasher::write_u128(self, i);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        FxHasher::write_usize(self, i);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        FxHasher::finish(self)
+    }
+}
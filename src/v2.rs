@@ -0,0 +1,382 @@
+//! Version 2 of the `fx` algorithm.
+//!
+//! Version 1 returns its state as the hash, and since the state is the result
+//! of a multiplication, its low bits only depend on the low bits of the input.
+//! Hash tables indexing buckets by the low bits of the hash then cluster, for
+//! instance when keys are multiples of a power of two. Version 2 keeps a
+//! single multiplication per word, with an addition instead of a rotation and
+//! a xor, and mixes the state in `finish` with two folded multiplications, so
+//! that every bit of the input affects every bit of the hash.
+//!
+//! `write` also packs the trailing bytes of a buffer together with their count
+//! into a single word, like [`FxLengthAwareHasher`](crate::FxLengthAwareHasher).
+//! Buffers of 128 bytes or more first go through a faster path, which hashes
+//! blocks of four words in two independent lanes.
+//!
+//! The hashes produced by this module are frozen: they will stay the same in
+//! every future release, so they can be persisted.
+//!
+//! # Example
+//!
+//! Flipping one bit of a key flips each bit of a `v2` hash about half of the
+//! time, while many bits of a `v1` hash never change.
+//!
+//! ```rust
+//! use std::hash::Hasher;
+//! use std::mem::size_of;
+//! use rustc_hash::{v1, v2};
+//!
+//! // Returns the largest deviation from one half of the probability that a
+//! // bit of the hash flips when a bit of the key is flipped.
+//! fn worst_bias<H: Hasher + Default>() -> f64 {
+//!     let hash = |key| {
+//!         let mut hasher = H::default();
+//!         hasher.write_u64(key);
+//!         hasher.finish()
+//!     };
+//!     let samples = 500;
+//!     let mut flips = [[0u32; 64]; 64];
+//!     let mut key = 0x0123456789abcdefu64;
+//!     for n in 0..samples {
+//!         key = key.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+//!         // Use small keys half of the time.
+//!         let key = if n % 2 == 0 { key } else { key >> 48 };
+//!         for (i, flips) in flips.iter_mut().enumerate() {
+//!             let diff = hash(key) ^ hash(key ^ (1 << i));
+//!             for (o, flip) in flips.iter_mut().enumerate() {
+//!                 *flip += (diff >> o & 1) as u32;
+//!             }
+//!         }
+//!     }
+//!     let hash_bits = 8 * size_of::<usize>();
+//!     flips
+//!         .iter()
+//!         .flat_map(|flips| flips[..hash_bits].iter())
+//!         .map(|&n| (n as f64 / samples as f64 - 0.5).abs())
+//!         .fold(0.0, f64::max)
+//! }
+//!
+//! assert!(worst_bias::<v1::FxHasher>() > 0.45);
+//! assert!(worst_bias::<v2::FxHasher>() < 0.15);
+//! ```
+//!
+//! The hashes of integers are the same on all targets with the same pointer
+//! width, and are checked by the test suite:
+//!
+//! ```rust
+//! use std::hash::Hasher;
+//! use rustc_hash::v2::FxHasher;
+//!
+//! fn fx(hasher: FxHasher, f: impl Fn(&mut FxHasher)) -> u64 {
+//!     let mut hasher = hasher;
+//!     f(&mut hasher);
+//!     hasher.finish()
+//! }
+//!
+//! let ints = [
+//!     fx(FxHasher::default(), |_| {}),
+//!     fx(FxHasher::default(), |h| h.write_u8(0xab)),
+//!     fx(FxHasher::default(), |h| h.write_u32(0x12345678)),
+//!     fx(FxHasher::default(), |h| h.write_u64(0x0123456789abcdef)),
+//!     fx(FxHasher::default(), |h| h.write_u128(0x0123456789abcdeffedcba9876543210)),
+//!     fx(FxHasher::with_seed(42), |h| h.write_u64(7)),
+//! ];
+//! #[cfg(target_pointer_width = "64")]
+//! let expected = [
+//!     0xce2b556a866c35e5,
+//!     0x86238b40ebedc2c5,
+//!     0xa867e5ddd5084814,
+//!     0xe014b3a4ed6541a0,
+//!     0x49994278624a8d63,
+//!     0x6a871b85f82cc855,
+//! ];
+//! #[cfg(target_pointer_width = "32")]
+//! let expected = [0x27702713, 0xb7900172, 0xf072fccc, 0xd29a374e, 0xbd319afc, 0xcd86af5c];
+//! assert_eq!(ints, expected);
+//! ```
+//!
+//! Byte buffers are read in native endianness, so their hashes also depend on
+//! the endianness of the target. Buffers of at least 128 bytes go through the
+//! lanes:
+//!
+//! ```rust
+//! use std::hash::Hasher;
+//! use std::mem::size_of;
+//! use rustc_hash::v2::FxHasher;
+//!
+//! fn fx(bytes: &[u8]) -> u64 {
+//!     let mut hasher = FxHasher::default();
+//!     hasher.write(bytes);
+//!     hasher.finish()
+//! }
+//!
+//! let bytes: Vec<u8> = (0..200u32).map(|i| (i * 7 + 3) as u8).collect();
+//! #[cfg(all(target_pointer_width = "64", target_endian = "little"))]
+//! let expected = 0xa13afcf7ec0d3942;
+//! #[cfg(all(target_pointer_width = "64", target_endian = "big"))]
+//! let expected = 0x5de82dd9377333ce;
+//! #[cfg(all(target_pointer_width = "32", target_endian = "little"))]
+//! let expected = 0x87c3dbb8;
+//! #[cfg(all(target_pointer_width = "32", target_endian = "big"))]
+//! let expected = 0x02d30592;
+//! assert_eq!(fx(&bytes), expected);
+//!
+//! // Xoring the same value into the last word of a lane in one block and
+//! // into its first word in the next block changes the hash.
+//! let word = size_of::<usize>();
+//! let xor_word = |bytes: &mut [u8], index: usize, value: usize| {
+//!     let bytes = &mut bytes[index * word..][..word];
+//!     let mut w = [0; size_of::<usize>()];
+//!     w.copy_from_slice(bytes);
+//!     bytes.copy_from_slice(&(usize::from_ne_bytes(w) ^ value).to_ne_bytes());
+//! };
+//! let bytes: Vec<u8> = (0..256u32).map(|i| (i * 7 + 3) as u8).collect();
+//! for block in 0..256 / (4 * word) - 1 {
+//!     for &(last, first) in [(1, 4), (3, 6)].iter() {
+//!         for &value in [1, 0xff, usize::MAX].iter() {
+//!             let mut other = bytes.clone();
+//!             xor_word(&mut other, 4 * block + last, value);
+//!             xor_word(&mut other, 4 * block + first, value);
+//!             assert_ne!(fx(&bytes), fx(&other));
+//!         }
+//!     }
+//! }
+//!
+//! // A word equal to its key does not hide the other word of its lane, and
+//! // no fixed last block erases the blocks before it.
+//! let last_blocks: [[u64; 4]; 4] = [
+//!     [0; 4],
+//!     [0x082efa98ec4e6c89, 0, 0xbe5466cf34e90c6c, 0],
+//!     [0, 0x452821e638d01377, 0, 0xc0ac29b7c97c50dd],
+//!     [u64::MAX; 4],
+//! ];
+//! for last in last_blocks.iter() {
+//!     let hash = |fill| {
+//!         let mut bytes = vec![fill; 128];
+//!         for (i, word) in last.iter().enumerate() {
+//!             bytes[96 + 8 * i..][..8].copy_from_slice(&word.to_ne_bytes());
+//!         }
+//!         fx(&bytes)
+//!     };
+//!     assert_ne!(hash(0x00), hash(0xab));
+//! }
+//! ```
+
+use core::convert::TryInto;
+use core::default::Default;
+use core::hash::Hasher;
+use core::mem::size_of;
+
+/// A speedy hash algorithm for use within rustc, version 2.
+///
+/// See the [module documentation](self) for how it differs from
+/// [`v1::FxHasher`](crate::v1::FxHasher).
+#[derive(Clone, Debug)]
+pub struct FxHasher {
+    hash: usize,
+}
+
+#[cfg(target_pointer_width = "32")]
+const K: usize = 0x93d765dd;
+#[cfg(target_pointer_width = "64")]
+const K: usize = 0xf1357aea2e62a9c5;
+
+#[cfg(target_pointer_width = "32")]
+const K_FINISH: usize = 0x9e3779b9;
+#[cfg(target_pointer_width = "64")]
+const K_FINISH: usize = 0x9e3779b97f4a7c15;
+
+// Adding a zero word to a zero state leaves it at zero, so start from the
+// leading digits of the fractional part of pi instead.
+#[cfg(target_pointer_width = "32")]
+const INITIAL_STATE: usize = 0x243f6a88;
+#[cfg(target_pointer_width = "64")]
+const INITIAL_STATE: usize = 0x243f6a8885a308d3;
+
+// Buffers at least this long go through `FxHasher::write_blocks`.
+const BULK_THRESHOLD: usize = 128;
+
+// More digits of pi, keying the lanes of `FxHasher::write_blocks`.
+#[cfg(target_pointer_width = "32")]
+const LANE_KEYS: [usize; 2] = [0x85a308d3, 0x13198a2e];
+#[cfg(target_pointer_width = "64")]
+const LANE_KEYS: [usize; 2] = [0x13198a2e03707344, 0xa4093822299f31d0];
+
+// The digits of pi that follow, keying the words of each block.
+#[cfg(target_pointer_width = "32")]
+const BLOCK_KEYS: [usize; 4] = [0x03707344, 0xa4093822, 0x299f31d0, 0x082efa98];
+#[cfg(target_pointer_width = "64")]
+const BLOCK_KEYS: [usize; 4] = [
+    0x082efa98ec4e6c89,
+    0x452821e638d01377,
+    0xbe5466cf34e90c6c,
+    0xc0ac29b7c97c50dd,
+];
+
+impl Default for FxHasher {
+    #[inline]
+    fn default() -> FxHasher {
+        FxHasher {
+            hash: INITIAL_STATE,
+        }
+    }
+}
+
+impl FxHasher {
+    /// Creates a `FxHasher` whose state starts from `seed`.
+    ///
+    /// Only the low 32 bits of the seed are used on 32-bit targets.
+    #[inline]
+    pub fn with_seed(seed: u64) -> FxHasher {
+        FxHasher {
+            hash: seed as usize,
+        }
+    }
+
+    #[inline]
+    fn add_to_hash(&mut self, i: usize) {
+        self.hash = self.hash.wrapping_add(i).wrapping_mul(K);
+    }
+}
+
+/// Multiplies `x` by `y` to twice the width of a word, and xors the two halves.
+#[inline]
+fn folded_multiply(x: usize, y: usize) -> usize {
+    #[cfg(target_pointer_width = "32")]
+    let (full, bits) = ((x as u64) * (y as u64), 32);
+    #[cfg(target_pointer_width = "64")]
+    let (full, bits) = ((x as u128) * (y as u128), 64);
+    (full as usize) ^ ((full >> bits) as usize)
+}
+
+#[inline]
+fn read_usize(bytes: &[u8]) -> usize {
+    #[cfg(target_pointer_width = "32")]
+    let word = u32::from_ne_bytes(bytes[..4].try_into().unwrap());
+    #[cfg(target_pointer_width = "64")]
+    let word = u64::from_ne_bytes(bytes[..8].try_into().unwrap());
+    word as usize
+}
+
+/// Absorbs two words of a block into a lane.
+///
+/// Both words go through the same folded multiplication, so a change to
+/// either of them changes its product in a way that a change to the next
+/// block cannot undo without knowing the lane. The words are xored back into
+/// the product, since a word equal to its key zeroes it whatever the other
+/// word is. The lane is then mixed with the product by a folded
+/// multiplication with `K`, which makes the contribution of a block depend on
+/// its position.
+#[inline]
+fn absorb(lane: usize, a: usize, b: usize, keys: [usize; 2]) -> usize {
+    let product = folded_multiply(a ^ keys[0], b ^ keys[1]) ^ a ^ b;
+    folded_multiply(lane ^ product, K)
+}
+
+impl FxHasher {
+    /// Hashes the whole blocks of four words at the start of `bytes` and
+    /// returns the bytes left over.
+    ///
+    /// Each block feeds two lanes with two words each, see `absorb`. The
+    /// products of the words do not depend on the lanes, and the lanes do
+    /// not depend on each other, so unlike `add_to_hash` the processor can
+    /// work on several words at once.
+    #[inline]
+    fn write_blocks<'a>(&mut self, mut bytes: &'a [u8]) -> &'a [u8] {
+        let word = size_of::<usize>();
+        let mut lane0 = self.hash ^ LANE_KEYS[0];
+        let mut lane1 = self.hash ^ LANE_KEYS[1];
+        while bytes.len() >= 4 * word {
+            let w0 = read_usize(bytes);
+            let w1 = read_usize(&bytes[word..]);
+            let w2 = read_usize(&bytes[2 * word..]);
+            let w3 = read_usize(&bytes[3 * word..]);
+            lane0 = absorb(lane0, w0, w1, [BLOCK_KEYS[0], BLOCK_KEYS[1]]);
+            lane1 = absorb(lane1, w2, w3, [BLOCK_KEYS[2], BLOCK_KEYS[3]]);
+            bytes = &bytes[4 * word..];
+        }
+        self.add_to_hash(lane0);
+        self.add_to_hash(lane1);
+        bytes
+    }
+}
+
+impl Hasher for FxHasher {
+    #[inline]
+    fn write(&mut self, mut bytes: &[u8]) {
+        let mut hash = FxHasher { hash: self.hash };
+        assert!(size_of::<usize>() <= 8);
+        if bytes.len() >= BULK_THRESHOLD {
+            hash.add_to_hash(bytes.len());
+            bytes = hash.write_blocks(bytes);
+        }
+        while bytes.len() >= size_of::<usize>() {
+            hash.add_to_hash(read_usize(bytes));
+            bytes = &bytes[size_of::<usize>()..];
+        }
+
+        // The tail is shorter than a word, so it fits below the top byte,
+        // which records its length.
+        let mut tail = bytes.len() << ((size_of::<usize>() - 1) * 8);
+        let mut shift = 0;
+        if (size_of::<usize>() > 4) && (bytes.len() >= 4) {
+            tail |= u32::from_ne_bytes(bytes[..4].try_into().unwrap()) as usize;
+            shift += 32;
+            bytes = &bytes[4..];
+        }
+        if (size_of::<usize>() > 2) && bytes.len() >= 2 {
+            tail |= (u16::from_ne_bytes(bytes[..2].try_into().unwrap()) as usize) << shift;
+            shift += 16;
+            bytes = &bytes[2..];
+        }
+        if (size_of::<usize>() > 1) && !bytes.is_empty() {
+            tail |= (bytes[0] as usize) << shift;
+        }
+        hash.add_to_hash(tail);
+        self.hash = hash.hash;
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.add_to_hash(i as usize);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.add_to_hash(i as usize);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.add_to_hash(i as usize);
+    }
+
+    #[cfg(target_pointer_width = "32")]
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.add_to_hash(i as usize);
+        self.add_to_hash((i >> 32) as usize);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.add_to_hash(i as usize);
+    }
+
+    #[inline]
+    fn write_u128(&mut self, i: u128) {
+        self.write_u64(i as u64);
+        self.write_u64((i >> 64) as u64);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.add_to_hash(i);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        folded_multiply(folded_multiply(self.hash, K_FINISH), K) as u64
+    }
+}
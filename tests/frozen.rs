@@ -0,0 +1,42 @@
+#![cfg(any(feature = "std", feature = "hashbrown"))]
+
+use rustc_hash::{FrozenFxMap, FxHashMap};
+
+fn check(keys: impl Iterator<Item = String>) {
+    let map: FxHashMap<String, usize> = keys.enumerate().map(|(i, key)| (key, i)).collect();
+    let frozen = FrozenFxMap::from(map.clone());
+    assert!(frozen.is_perfect());
+    assert_eq!(frozen.len(), map.len());
+    for (key, value) in map.iter() {
+        assert_eq!(frozen.get(key.as_str()), Some(value));
+    }
+    assert_eq!(frozen.get("missing"), None);
+}
+
+#[test]
+fn similar_keys_get_a_perfect_hash() {
+    check((100000..102000).map(|i| format!("key{}", i)));
+    check((0..100000).map(|i| format!("key{}", i)));
+    check((0..5000).map(|i| format!("intrinsic_{}", i)));
+}
+
+#[test]
+fn integer_keys_get_a_perfect_hash() {
+    let frozen: FrozenFxMap<u64, u64> = (0..10000).map(|i| (i << 32, i)).collect();
+    assert!(frozen.is_perfect());
+    assert!((0..10000).all(|i| frozen[&(i << 32)] == i));
+}
+
+#[test]
+fn equal_hashes_fall_back_to_a_hash_table() {
+    #[derive(PartialEq, Eq)]
+    struct Key(u32);
+
+    impl std::hash::Hash for Key {
+        fn hash<H: std::hash::Hasher>(&self, _: &mut H) {}
+    }
+
+    let frozen: FrozenFxMap<Key, u32> = (0..10).map(|i| (Key(i), i)).collect();
+    assert!(!frozen.is_perfect());
+    assert!((0..10).all(|i| frozen[&Key(i)] == i));
+}
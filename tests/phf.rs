@@ -0,0 +1,32 @@
+use rustc_hash::phf::Table;
+
+fn similar_keys(prefix: &str, range: std::ops::Range<u32>) -> Vec<String> {
+    range.map(|i| format!("{}{}", prefix, i)).collect()
+}
+
+fn check<const N: usize>(keys: &[String]) {
+    let keys: Vec<&str> = keys.iter().map(|key| key.as_str()).collect();
+    let table = Table::<N>::build(&keys);
+    let mut seen = [false; N];
+    for &slot in table.slots.iter() {
+        assert!(!seen[slot as usize]);
+        seen[slot as usize] = true;
+    }
+}
+
+#[test]
+fn similar_keys_get_a_perfect_hash() {
+    check::<2000>(&similar_keys("key", 100000..102000));
+    check::<5000>(&similar_keys("intrinsic_", 0..5000));
+}
+
+#[test]
+#[should_panic(expected = "duplicate key")]
+fn duplicate_keys_are_rejected() {
+    check::<3>(
+        &similar_keys("key", 0..2)
+            .into_iter()
+            .chain(Some("key1".to_string()))
+            .collect::<Vec<_>>(),
+    );
+}
@@ -0,0 +1,69 @@
+#![cfg(feature = "shuffle-iteration")]
+
+use std::env;
+use std::process::{Command, Output};
+
+use rustc_hash::FxHashSet;
+
+/// Runs `print_order` in a new process, since the seed is read once per
+/// process.
+fn run(seed: &str) -> Output {
+    Command::new(env::current_exe().unwrap())
+        .args([
+            "print_order",
+            "--exact",
+            "--ignored",
+            "--nocapture",
+            "--test-threads=1",
+        ])
+        .env("RUSTC_HASH_SEED", seed)
+        .output()
+        .unwrap()
+}
+
+fn order(seed: &str) -> String {
+    let output = run(seed);
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    // The harness may print the name of the test on the same line.
+    let line = stdout.lines().find_map(|line| line.split("order: ").nth(1));
+    line.expect("no order printed").to_string()
+}
+
+#[test]
+#[ignore = "run by the other tests in a new process"]
+fn print_order() {
+    let set: FxHashSet<u32> = (0..32).collect();
+    println!("order: {:?}", set.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn same_seed_replays_the_same_order() {
+    let first = order("7");
+    assert_eq!(order("7"), first);
+    assert_eq!(order("0x7"), first);
+    assert!(["8", "9", "10"].iter().any(|seed| order(seed) != first));
+}
+
+#[test]
+fn seed_is_printed() {
+    let output = run("0x2a");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("replay with RUSTC_HASH_SEED=42"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn invalid_seed_panics() {
+    let output = run("seven");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("RUSTC_HASH_SEED must be an integer"),
+        "{}",
+        stderr
+    );
+}